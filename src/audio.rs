@@ -0,0 +1,481 @@
+use crate::config::AudioConfig;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+const WINDOW: usize = 1024; // Samples per FFT, must be a power of two
+const MIN_FREQ: f32 = 40.0;
+const MAX_FREQ: f32 = 16_000.0;
+const BEAT_HISTORY: usize = 43; // About one second of windows at 44.1kHz
+
+/// Where the audio comes from.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioInput {
+    /// The default PipeWire/PulseAudio monitor, i.e. whatever is playing.
+    #[default]
+    Monitor,
+    /// Raw signed 16-bit little-endian PCM on stdin.
+    Stdin,
+    /// A 16-bit PCM WAV file, played back in real time.
+    Wav,
+}
+
+/// Anything that yields mono samples in -1.0..=1.0.
+pub trait AudioSource: Send {
+    fn sample_rate(&self) -> u32;
+    /// Fills `buf` with samples, returning how many were written. Zero means the source has ended.
+    fn read(&mut self, buf: &mut [f32]) -> usize;
+}
+
+/// Reads interleaved signed 16-bit little-endian PCM and downmixes it to mono.
+pub struct PcmSource<R: Read + Send> {
+    reader: R,
+    channels: usize,
+    sample_rate: u32,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read + Send> PcmSource<R> {
+    pub fn new(reader: R, channels: u16, sample_rate: u32) -> Self {
+        Self {
+            reader,
+            channels: channels.max(1) as usize,
+            sample_rate,
+            bytes: Vec::new(),
+        }
+    }
+}
+
+impl<R: Read + Send> AudioSource for PcmSource<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buf: &mut [f32]) -> usize {
+        let frame_bytes = self.channels * 2;
+        self.bytes.resize(buf.len() * frame_bytes, 0);
+        let mut filled = 0;
+        while filled < self.bytes.len() {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => filled += n,
+            }
+        }
+        let frames = filled / frame_bytes;
+        for (i, frame) in self.bytes[..frames * frame_bytes].chunks_exact(frame_bytes).enumerate() {
+            let sum: i32 = frame
+                .chunks_exact(2)
+                .map(|s| i16::from_le_bytes([s[0], s[1]]) as i32)
+                .sum();
+            buf[i] = sum as f32 / (self.channels as f32 * i16::MAX as f32);
+        }
+        frames
+    }
+}
+
+/// Opens a 16-bit PCM WAV file, positioned at the start of its sample data.
+pub fn open_wav(path: &Path) -> Result<PcmSource<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open WAV file at {:?}", path))?;
+    let mut reader = BufReader::new(file);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).context("WAV file is too short")?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        bail!("{:?} is not a WAV file", path);
+    }
+    let mut format: Option<(u16, u32)> = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        reader.read_exact(&mut chunk).context("WAV file has no data chunk")?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as usize;
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut fmt = vec![0u8; size];
+                reader.read_exact(&mut fmt).context("WAV fmt chunk is truncated")?;
+                if fmt.len() < 16 {
+                    bail!("WAV fmt chunk is too short");
+                }
+                let audio_format = u16::from_le_bytes([fmt[0], fmt[1]]);
+                let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                if audio_format != 1 || bits != 16 {
+                    bail!("Only 16-bit PCM WAV files are supported");
+                }
+                format = Some((channels, sample_rate));
+            }
+            b"data" => {
+                let (channels, sample_rate) = format.context("WAV data chunk comes before fmt chunk")?;
+                return Ok(PcmSource::new(reader, channels, sample_rate));
+            }
+            _ => {
+                // Chunks are padded to an even length
+                std::io::copy(&mut (&mut reader).take((size + size % 2) as u64), &mut std::io::sink())?;
+            }
+        }
+    }
+}
+
+/// `parec`'s output, which stops the recording when dropped.
+pub struct MonitorStream {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Read for MonitorStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl Drop for MonitorStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Records the default monitor source with `parec`, which works on PulseAudio and on PipeWire via pipewire-pulse.
+pub fn open_monitor(sample_rate: u32) -> Result<PcmSource<MonitorStream>> {
+    let mut child = Command::new("parec")
+        .args([
+            "--device=@DEFAULT_MONITOR@",
+            "--format=s16le",
+            "--channels=1",
+            &format!("--rate={}", sample_rate),
+            "--raw",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start parec to capture the audio monitor")?;
+    let Some(stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        let _ = child.wait();
+        bail!("parec has no stdout");
+    };
+    Ok(PcmSource::new(MonitorStream { child, stdout }, 1, sample_rate))
+}
+
+/// Opens the source selected in config.
+pub fn open_source(config: &AudioConfig, path: Option<&Path>) -> Result<Box<dyn AudioSource>> {
+    let sample_rate = config.sample_rate.unwrap_or(44_100);
+    Ok(match config.input.unwrap_or_default() {
+        AudioInput::Monitor => Box::new(open_monitor(sample_rate)?),
+        AudioInput::Stdin => Box::new(PcmSource::new(
+            std::io::stdin(),
+            config.channels.unwrap_or(1),
+            sample_rate,
+        )),
+        AudioInput::Wav => Box::new(open_wav(path.context("audio input \"wav\" needs a path")?)?),
+    })
+}
+
+/// Per-window analysis result sent to the render loop.
+#[derive(Clone, Debug, Default)]
+pub struct AudioFrame {
+    /// Energy per band from bass to treble, normalised to roughly 0.0 - 1.0.
+    pub bands: Vec<f32>,
+    pub beat: bool,
+}
+
+/// Splits audio into log-spaced frequency bands and detects beats from bass energy.
+pub struct AudioAnalyzer {
+    band_edges: Vec<usize>, // FFT bin boundaries, one more than the number of bands
+    peak: f32,              // Slowly decaying maximum band energy used for auto gain
+    bass_history: Vec<f32>,
+    gain: f32,
+    beat_sensitivity: f32,
+    was_beat: bool,
+    window: Vec<f32>,
+}
+
+impl AudioAnalyzer {
+    pub fn new(bands: usize, sample_rate: u32, gain: f32, beat_sensitivity: f32) -> Self {
+        let bands = bands.max(1);
+        let bin_hz = sample_rate as f32 / WINDOW as f32;
+        let max_freq = MAX_FREQ.min(sample_rate as f32 / 2.0);
+        let mut band_edges: Vec<usize> = (0..=bands)
+            .map(|i| {
+                let freq = MIN_FREQ * (max_freq / MIN_FREQ).powf(i as f32 / bands as f32);
+                ((freq / bin_hz).round() as usize).clamp(1, WINDOW / 2)
+            })
+            .collect();
+        // Low bands can round to the same bin; keep every band at least one bin wide
+        for i in 1..band_edges.len() {
+            if band_edges[i] <= band_edges[i - 1] {
+                band_edges[i] = band_edges[i - 1] + 1;
+            }
+        }
+        // Hann window to reduce spectral leakage
+        let window = (0..WINDOW)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (WINDOW - 1) as f32).cos())
+            .collect();
+        Self {
+            band_edges,
+            peak: 1e-3,
+            bass_history: Vec::with_capacity(BEAT_HISTORY),
+            gain,
+            beat_sensitivity,
+            was_beat: false,
+            window,
+        }
+    }
+
+    /// Analyses one window of `WINDOW` samples.
+    pub fn analyze(&mut self, samples: &[f32]) -> AudioFrame {
+        let mut re: Vec<f32> = samples.iter().zip(&self.window).map(|(s, w)| s * w).collect();
+        re.resize(WINDOW, 0.0);
+        let mut im = vec![0.0; WINDOW];
+        fft(&mut re, &mut im);
+
+        let energies: Vec<f32> = self
+            .band_edges
+            .windows(2)
+            .map(|edge| {
+                // Use the strongest bin so wide treble bands aren't diluted
+                (edge[0]..edge[1].min(WINDOW / 2))
+                    .map(|b| (re[b] * re[b] + im[b] * im[b]).sqrt())
+                    .fold(0.0, f32::max)
+            })
+            .collect();
+        let loudest = energies.iter().copied().fold(0.0, f32::max);
+        self.peak = (self.peak * 0.995).max(loudest).max(1e-3);
+        let bands: Vec<f32> = energies.iter().map(|e| (e / self.peak * self.gain).min(1.0)).collect();
+
+        // Compare bass energy against its recent average
+        let bass_bands = (bands.len() / 4).max(1);
+        let bass = bands[..bass_bands].iter().sum::<f32>() / bass_bands as f32;
+        let average = if self.bass_history.is_empty() {
+            bass
+        } else {
+            self.bass_history.iter().sum::<f32>() / self.bass_history.len() as f32
+        };
+        let loud = self.bass_history.len() == BEAT_HISTORY && bass > 0.3 && bass > average * self.beat_sensitivity;
+        // A kick usually spans two windows; only report its onset
+        let beat = loud && !self.was_beat;
+        self.was_beat = loud;
+        if self.bass_history.len() == BEAT_HISTORY {
+            self.bass_history.remove(0);
+        }
+        self.bass_history.push(bass);
+
+        AudioFrame { bands, beat }
+    }
+}
+
+/// Maps band energies onto a row of heat values, bass on the left and treble on the right.
+pub fn bands_to_row(bands: &[f32], width: usize, max_heat: u8) -> Vec<u8> {
+    if bands.is_empty() {
+        return vec![0; width];
+    }
+    (0..width)
+        .map(|x| {
+            // Interpolate between band centres so the row has no hard steps
+            let pos = (x as f32 + 0.5) / width as f32 * bands.len() as f32 - 0.5;
+            let i = pos.floor().clamp(0.0, (bands.len() - 1) as f32) as usize;
+            let j = (i + 1).min(bands.len() - 1);
+            let t = (pos - i as f32).clamp(0.0, 1.0);
+            let energy = bands[i] * (1.0 - t) + bands[j] * t;
+            (energy * max_heat as f32).round() as u8
+        })
+        .collect()
+}
+
+/// Reads and analyses audio on a background thread, sending a frame per window.
+/// WAV files are paced to real time; live sources pace themselves.
+pub fn spawn_audio_thread(mut source: Box<dyn AudioSource>, config: &AudioConfig) -> mpsc::Receiver<AudioFrame> {
+    let (tx, rx) = mpsc::channel();
+    let paced = config.input == Some(AudioInput::Wav);
+    let mut analyzer = AudioAnalyzer::new(
+        config.bands.unwrap_or(16),
+        source.sample_rate(),
+        config.gain.unwrap_or(1.0),
+        config.beat_sensitivity.unwrap_or(1.4),
+    );
+    std::thread::spawn(move || {
+        let window_time = Duration::from_secs_f32(WINDOW as f32 / source.sample_rate().max(1) as f32);
+        let mut buf = vec![0.0; WINDOW];
+        let mut next = Instant::now();
+        loop {
+            let read = source.read(&mut buf);
+            if read == 0 {
+                eprintln!("[DEBUG] Audio source ended");
+                break;
+            }
+            if tx.send(analyzer.analyze(&buf[..read])).is_err() {
+                break;
+            }
+            if paced {
+                next += window_time;
+                if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// In-place iterative radix-2 FFT. Both slices must have the same power-of-two length.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const RATE: u32 = 44_100;
+    const BANDS: usize = 8;
+
+    /// Writes a 16-bit PCM WAV with an odd-sized chunk before the data, to check chunks are skipped with padding.
+    fn write_wav(name: &str, channels: u16, bits: u16, samples: &[i16]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("doomfire-audio-{}-{}.wav", name, std::process::id()));
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(4 + 24 + 12 + 8 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(b"fmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&RATE.to_le_bytes());
+        wav.extend_from_slice(&(RATE * channels as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&bits.to_le_bytes());
+        wav.extend_from_slice(b"LIST");
+        wav.extend_from_slice(&3u32.to_le_bytes());
+        wav.extend_from_slice(b"abc\0");
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+        std::fs::write(&path, wav).unwrap();
+        path
+    }
+
+    fn sine(freq: f32, seconds: f32) -> Vec<i16> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|i| ((2.0 * PI * freq * i as f32 / RATE as f32).sin() * 16_000.0) as i16)
+            .collect()
+    }
+
+    /// The band the analyzer files `freq` under, from the log spacing between MIN_FREQ and MAX_FREQ.
+    fn band_of(freq: f32) -> usize {
+        ((freq / MIN_FREQ).ln() / (MAX_FREQ / MIN_FREQ).ln() * BANDS as f32) as usize
+    }
+
+    fn loudest_band(frame: &AudioFrame) -> usize {
+        (0..frame.bands.len()).max_by(|&a, &b| frame.bands[a].total_cmp(&frame.bands[b])).unwrap()
+    }
+
+    #[test]
+    fn sine_lands_in_its_band() {
+        // Frequencies near the middle of bands 1, 4 and 6
+        for freq in [123.0, 1164.0, 5212.0] {
+            // Stereo, to go through the downmix as well
+            let samples: Vec<i16> = sine(freq, 0.1).iter().flat_map(|&s| [s, s]).collect();
+            let path = write_wav(&format!("sine-{}", freq), 2, 16, &samples);
+            let mut source = open_wav(&path).unwrap();
+            assert_eq!(source.sample_rate(), RATE);
+            let mut buf = vec![0.0; WINDOW];
+            assert_eq!(source.read(&mut buf), WINDOW);
+            let mut analyzer = AudioAnalyzer::new(BANDS, RATE, 1.0, 1.4);
+            let frame = analyzer.analyze(&buf);
+            assert_eq!(frame.bands.len(), BANDS);
+            assert_eq!(loudest_band(&frame), band_of(freq), "{} Hz", freq);
+            assert!(!frame.beat);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn clicks_are_beats() {
+        // A 60 Hz thump twice a second over silence, for three seconds
+        let thump = sine(60.0, 0.05);
+        let mut samples = vec![0i16; RATE as usize * 3];
+        for start in (0..samples.len() - thump.len()).step_by(RATE as usize / 2) {
+            samples[start..start + thump.len()].copy_from_slice(&thump);
+        }
+        let path = write_wav("clicks", 1, 16, &samples);
+        let mut source = open_wav(&path).unwrap();
+        let mut analyzer = AudioAnalyzer::new(BANDS, RATE, 1.0, 1.4);
+        let mut buf = vec![0.0; WINDOW];
+        let (mut windows, mut beats) = (0, 0);
+        loop {
+            let read = source.read(&mut buf);
+            if read == 0 {
+                break;
+            }
+            windows += 1;
+            if analyzer.analyze(&buf[..read]).beat {
+                beats += 1;
+            }
+        }
+        // No beats during the first second while the history fills, then one per thump
+        assert!(windows > 120);
+        assert!((3..=4).contains(&beats), "{} beats", beats);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_unsupported_wavs() {
+        let path = write_wav("8bit", 1, 8, &[0; 64]);
+        assert!(open_wav(&path).is_err());
+        std::fs::write(&path, b"RIFF\0\0\0\0AVI LIST").unwrap();
+        assert!(open_wav(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bands_to_row_edges() {
+        assert!(bands_to_row(&[0.5, 1.0], 0, 36).is_empty());
+        assert_eq!(bands_to_row(&[], 3, 36), vec![0, 0, 0]);
+        // One band fills the whole row
+        assert_eq!(bands_to_row(&[0.5], 4, 36), vec![18; 4]);
+        assert_eq!(bands_to_row(&[1.0], 1, 36), vec![36]);
+        // Outer cells sit on the outer band centres
+        let row = bands_to_row(&[0.0, 1.0], 4, 36);
+        assert_eq!((row[0], row[3]), (0, 36));
+        assert!(row[1] < row[2]);
+    }
+}
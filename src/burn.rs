@@ -0,0 +1,167 @@
+use crate::config::BurnConfig;
+use crate::effect::Effect;
+use image::{DynamicImage, GenericImageView};
+use serde::Deserialize;
+
+/// How long a window takes to burn away from the bottom up.
+const BURN_SECONDS: f32 = 0.75;
+
+/// How a screenshot pixel is turned into heat.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatMapping {
+    /// Manhattan RGB distance from the background colour.
+    #[default]
+    ColorDistance,
+    /// Perceptual (Rec. 709) luminance difference from the background colour.
+    Luminance,
+    /// Sobel edge strength of the luminance, so text and outlines burn brightest.
+    Edges,
+}
+
+/// Heat mapping mode plus the curve applied to its output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurnStyle {
+    pub mapping: HeatMapping,
+    /// Exponent applied to the 0.0 - 1.0 heat; above 1 only strong features burn hot.
+    pub gamma: f32,
+    /// Heat below this (0.0 - 1.0) doesn't ignite at all.
+    pub threshold: f32,
+}
+
+impl Default for BurnStyle {
+    fn default() -> Self {
+        Self { mapping: HeatMapping::ColorDistance, gamma: 1.0, threshold: 0.0 }
+    }
+}
+
+impl BurnStyle {
+    pub fn from_config(config: Option<&BurnConfig>) -> Self {
+        let default = Self::default();
+        match config {
+            Some(c) => Self {
+                mapping: c.mapping.unwrap_or(default.mapping),
+                gamma: c.gamma.unwrap_or(default.gamma).max(0.01),
+                threshold: c.threshold.unwrap_or(default.threshold).clamp(0.0, 0.99),
+            },
+            None => default,
+        }
+    }
+
+    /// Applies threshold and gamma to a 0.0 - 1.0 value and scales it to a palette index.
+    fn shape(&self, value: f32, max_idx: f32) -> u8 {
+        if value < self.threshold {
+            return 0;
+        }
+        let value = ((value - self.threshold) / (1.0 - self.threshold)).clamp(0.0, 1.0);
+        (value.powf(self.gamma) * max_idx).round() as u8
+    }
+}
+
+/// A rectangle of the fire grid, in cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Burns a screenshot region into the fire a few rows per frame, bottom first,
+/// so a closed window looks like it burns away rather than the whole screen flashing.
+pub struct BurnIn {
+    rect: CellRect,
+    heat: Vec<u8>, // Heat per cell of `rect`, row-major
+    rows_done: usize,
+    rows_per_frame: usize,
+}
+
+impl BurnIn {
+    /// Converts the part of `screenshot` under `rect` into heat. The screenshot covers the whole output.
+    pub fn new(
+        screenshot: &DynamicImage,
+        rect: CellRect,
+        fire: &dyn Effect,
+        background: [u8; 3],
+        style: &BurnStyle,
+        fps: u32,
+    ) -> Self {
+        let resized = screenshot.resize_exact(
+            fire.width() as u32,
+            fire.height() as u32,
+            image::imageops::FilterType::Triangle,
+        );
+        let max_idx = fire.max_heat() as f32;
+        let heat = heat_map(&resized, rect, background, style)
+            .into_iter()
+            .map(|v| style.shape(v, max_idx))
+            .collect();
+        let frames = (fps.max(1) as f32 * BURN_SECONDS).max(1.0);
+        Self {
+            rect,
+            heat,
+            rows_done: 0,
+            rows_per_frame: (rect.height as f32 / frames).ceil().max(1.0) as usize,
+        }
+    }
+
+    /// Ignites the next band of rows. Returns false once the whole region has burned.
+    pub fn step(&mut self, fire: &mut dyn Effect) -> bool {
+        let end = (self.rows_done + self.rows_per_frame).min(self.rect.height);
+        for row in self.rows_done..end {
+            // Rows are counted from the bottom of the region
+            let ry = self.rect.height - 1 - row;
+            let y = self.rect.y + ry;
+            for rx in 0..self.rect.width {
+                let heat = self.heat[ry * self.rect.width + rx];
+                fire.add_heat(self.rect.x + rx, y, heat);
+            }
+        }
+        self.rows_done = end;
+        self.rows_done < self.rect.height
+    }
+}
+
+/// Maps the pixels under `rect` to 0.0 - 1.0 heat, row-major.
+pub fn heat_map(image: &DynamicImage, rect: CellRect, background: [u8; 3], style: &BurnStyle) -> Vec<f32> {
+    let (w, h) = image.dimensions();
+    let pixel = |x: usize, y: usize| {
+        let px = image.get_pixel((x as u32).min(w - 1), (y as u32).min(h - 1));
+        [px[0], px[1], px[2]]
+    };
+    let mut heat = Vec::with_capacity(rect.width * rect.height);
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            heat.push(match style.mapping {
+                HeatMapping::ColorDistance => color_distance(pixel(x, y), background),
+                HeatMapping::Luminance => (luminance(pixel(x, y)) - luminance(background)).abs(),
+                HeatMapping::Edges => {
+                    // 3x3 Sobel over luminance, clamping at the image border
+                    let l = |dx: isize, dy: isize| {
+                        let sx = (x as isize + dx).max(0) as usize;
+                        let sy = (y as isize + dy).max(0) as usize;
+                        luminance(pixel(sx, sy))
+                    };
+                    let gx = l(1, -1) + 2.0 * l(1, 0) + l(1, 1) - l(-1, -1) - 2.0 * l(-1, 0) - l(-1, 1);
+                    let gy = l(-1, 1) + 2.0 * l(0, 1) + l(1, 1) - l(-1, -1) - 2.0 * l(0, -1) - l(1, -1);
+                    // A hard black/white edge gives a magnitude of 4
+                    ((gx * gx + gy * gy).sqrt() / 4.0).min(1.0)
+                }
+            });
+        }
+    }
+    heat
+}
+
+/// Rec. 709 luminance of an sRGB pixel, 0.0 - 1.0.
+fn luminance(px: [u8; 3]) -> f32 {
+    (0.2126 * px[0] as f32 + 0.7152 * px[1] as f32 + 0.0722 * px[2] as f32) / 255.0
+}
+
+/// Manhattan RGB distance between two pixels, 0.0 - 1.0.
+fn color_distance(px: [u8; 3], background: [u8; 3]) -> f32 {
+    let r_diff = (px[0] as i32 - background[0] as i32).abs();
+    let g_diff = (px[1] as i32 - background[1] as i32).abs();
+    let b_diff = (px[2] as i32 - background[2] as i32).abs();
+    (r_diff + g_diff + b_diff) as f32 / (255.0 * 3.0)
+}
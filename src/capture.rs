@@ -0,0 +1,383 @@
+use anyhow::{anyhow, bail, Context, Result};
+use image::{DynamicImage, RgbImage};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::AsFd;
+use std::path::PathBuf;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle, WEnum};
+use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1};
+use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
+
+/// Which screenshot backend screen burn uses.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureBackend {
+    /// wlr-screencopy if the compositor supports it, otherwise grim.
+    #[default]
+    Auto,
+    Screencopy,
+    Grim,
+    /// Loads `<capture_dir>/<output>.png` instead of capturing, for testing without a compositor.
+    File,
+}
+
+/// Takes a screenshot of one output, scaled to roughly `width` x `height`.
+pub trait Capturer: Send {
+    fn capture(&mut self, output: &str, width: u32, height: u32) -> Result<DynamicImage>;
+}
+
+/// Builds the configured capturer. `Auto` falls back to grim when screencopy isn't available;
+/// asking for screencopy explicitly fails instead.
+pub fn make_capturer(backend: CaptureBackend, capture_dir: Option<PathBuf>) -> Result<Box<dyn Capturer>> {
+    Ok(match backend {
+        CaptureBackend::Grim => Box::new(GrimCapturer),
+        CaptureBackend::File => Box::new(FileCapturer {
+            dir: capture_dir.unwrap_or_else(|| PathBuf::from(".")),
+        }),
+        CaptureBackend::Screencopy => Box::new(ScreencopyCapturer::connect()?),
+        CaptureBackend::Auto => match ScreencopyCapturer::connect() {
+            Ok(capturer) => Box::new(capturer),
+            Err(e) => {
+                eprintln!("[DEBUG] Screencopy unavailable, using grim: {:#}", e);
+                Box::new(GrimCapturer)
+            }
+        },
+    })
+}
+
+/// Captures every output in `covered_outputs`, skipping (and logging) the ones that fail.
+pub fn take_screenshots_sync(
+    capturer: &mut dyn Capturer,
+    covered_outputs: &[(String, bool)],
+    (width, height): (u32, u32),
+) -> Vec<(String, DynamicImage)> {
+    let mut results = Vec::new();
+    for (name, _covered) in covered_outputs {
+        eprintln!("[DEBUG] Taking screenshot for output: {}", name);
+        match capturer.capture(name, width, height) {
+            Ok(i) => results.push((name.clone(), i)),
+            Err(e) => eprintln!("[DEBUG] {:#}", e),
+        }
+    }
+    results
+}
+
+/// Shells out to `grim -o <output> -` and decodes the PNG it writes.
+pub struct GrimCapturer;
+
+impl Capturer for GrimCapturer {
+    fn capture(&mut self, output: &str, _width: u32, _height: u32) -> Result<DynamicImage> {
+        let result = std::process::Command::new("grim")
+            .args(["-o", output, "-"])
+            .output()
+            .context("Failed to run grim")?;
+        if !result.status.success() {
+            bail!("grim failed for {}", output);
+        }
+        image::load_from_memory(&result.stdout).with_context(|| format!("Failed to decode screenshot for {}", output))
+    }
+}
+
+/// Reads screenshots from PNG files named after each output.
+pub struct FileCapturer {
+    pub dir: PathBuf,
+}
+
+impl Capturer for FileCapturer {
+    fn capture(&mut self, output: &str, _width: u32, _height: u32) -> Result<DynamicImage> {
+        let path = self.dir.join(format!("{}.png", output));
+        image::open(&path).with_context(|| format!("Failed to load screenshot at {:?}", path))
+    }
+}
+
+/// Captures outputs with wlr-screencopy into shared memory and samples them straight down
+/// to the requested size, skipping the PNG round trip.
+pub struct ScreencopyCapturer {
+    queue: EventQueue<CaptureState>,
+    state: CaptureState,
+    shm: wl_shm::WlShm,
+    manager: ZwlrScreencopyManagerV1,
+}
+
+#[derive(Default)]
+struct CaptureState {
+    outputs: Vec<(wl_output::WlOutput, Option<String>)>,
+    frame: FrameState,
+}
+
+#[derive(Default)]
+struct FrameState {
+    format: Option<wl_shm::Format>,
+    width: u32,
+    height: u32,
+    stride: u32,
+    y_invert: bool,
+    ready: bool,
+    failed: bool,
+}
+
+impl ScreencopyCapturer {
+    pub fn connect() -> Result<Self> {
+        let conn = Connection::connect_to_env().context("No Wayland display")?;
+        let (globals, mut queue) = registry_queue_init::<CaptureState>(&conn)?;
+        let qh = queue.handle();
+        let shm = globals.bind::<wl_shm::WlShm, _, _>(&qh, 1..=1, ())?;
+        let manager = globals
+            .bind::<ZwlrScreencopyManagerV1, _, _>(&qh, 1..=3, ())
+            .context("Compositor doesn't support wlr-screencopy")?;
+        let mut state = CaptureState::default();
+        for global in globals.contents().clone_list() {
+            if global.interface == "wl_output" {
+                // Version 4 is the first to announce output names
+                let output = globals.registry().bind::<wl_output::WlOutput, _, _>(
+                    global.name,
+                    global.version.min(4),
+                    &qh,
+                    state.outputs.len(),
+                );
+                state.outputs.push((output, None));
+            }
+        }
+        queue.roundtrip(&mut state)?;
+        Ok(Self { queue, state, shm, manager })
+    }
+}
+
+impl Capturer for ScreencopyCapturer {
+    fn capture(&mut self, output: &str, width: u32, height: u32) -> Result<DynamicImage> {
+        let qh = self.queue.handle();
+        let wl_output = self
+            .state
+            .outputs
+            .iter()
+            .find(|(_, name)| name.as_deref() == Some(output))
+            .map(|(o, _)| o.clone())
+            .ok_or_else(|| anyhow!("No Wayland output named {}", output))?;
+
+        self.state.frame = FrameState::default();
+        let frame = self.manager.capture_output(0, &wl_output, &qh, ());
+        // The compositor describes the buffer it wants straight away
+        self.queue.roundtrip(&mut self.state)?;
+        let format = self.state.frame.format.context("Compositor offered no shm buffer")?;
+        let (fw, fh, stride) = (self.state.frame.width, self.state.frame.height, self.state.frame.stride);
+        let size = (stride * fh) as usize;
+
+        let mut file = shm_file(size)?;
+        let pool = self.shm.create_pool(file.as_fd(), size as i32, &qh, ());
+        let buffer = pool.create_buffer(0, fw as i32, fh as i32, stride as i32, format, &qh, ());
+        frame.copy(&buffer);
+        while !self.state.frame.ready && !self.state.frame.failed {
+            self.queue.blocking_dispatch(&mut self.state)?;
+        }
+        frame.destroy();
+        buffer.destroy();
+        pool.destroy();
+        if self.state.frame.failed {
+            bail!("Screencopy failed for {}", output);
+        }
+
+        let mut data = vec![0u8; size];
+        file.read_exact(&mut data)?;
+        let rgb_order = match format {
+            // Little-endian ARGB is stored as B, G, R, A
+            wl_shm::Format::Argb8888 | wl_shm::Format::Xrgb8888 => [2, 1, 0],
+            wl_shm::Format::Abgr8888 | wl_shm::Format::Xbgr8888 => [0, 1, 2],
+            other => bail!("Unsupported screencopy format {:?}", other),
+        };
+
+        // Nearest-neighbour sample straight to the requested size
+        let (width, height) = (width.clamp(1, fw), height.clamp(1, fh));
+        let y_invert = self.state.frame.y_invert;
+        let img = RgbImage::from_fn(width, height, |x, y| {
+            let sx = x * fw / width;
+            let mut sy = y * fh / height;
+            if y_invert {
+                sy = fh - 1 - sy;
+            }
+            let i = (sy * stride + sx * 4) as usize;
+            image::Rgb([data[i + rgb_order[0]], data[i + rgb_order[1]], data[i + rgb_order[2]]])
+        });
+        Ok(DynamicImage::ImageRgb8(img))
+    }
+}
+
+/// Creates an unlinked file of `size` bytes in the runtime dir (tmpfs) to share with the compositor.
+fn shm_file(size: usize) -> Result<File> {
+    let dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let path = PathBuf::from(dir).join(format!("doomfire-screencopy-{}", std::process::id()));
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create shm file at {:?}", path))?;
+    std::fs::remove_file(&path)?;
+    file.set_len(size as u64)?;
+    Ok(file)
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for CaptureState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for CaptureState {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        idx: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event
+            && let Some(output) = state.outputs.get_mut(*idx)
+        {
+            output.1 = Some(name);
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for CaptureState {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let frame = &mut state.frame;
+        match event {
+            zwlr_screencopy_frame_v1::Event::Buffer { format: WEnum::Value(format), width, height, stride } => {
+                frame.format = Some(format);
+                frame.width = width;
+                frame.height = height;
+                frame.stride = stride;
+            }
+            zwlr_screencopy_frame_v1::Event::Flags { flags } => {
+                frame.y_invert = matches!(flags, WEnum::Value(f) if f.contains(zwlr_screencopy_frame_v1::Flags::YInvert));
+            }
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => frame.ready = true,
+            zwlr_screencopy_frame_v1::Event::Failed => frame.failed = true,
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(CaptureState: ignore wl_shm::WlShm);
+delegate_noop!(CaptureState: wl_shm_pool::WlShmPool);
+delegate_noop!(CaptureState: ignore wl_buffer::WlBuffer);
+delegate_noop!(CaptureState: ZwlrScreencopyManagerV1);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::burn::{BurnIn, BurnStyle, CellRect};
+    use crate::config::Config;
+    use crate::doom_fire::DoomFire;
+    use crate::effect::Effect;
+    use image::Rgb;
+
+    const WIDTH: u32 = 64;
+    const HEIGHT: u32 = 36;
+
+    /// A black screen with a white window from (16, 8) to (48, 28), saved as `<dir>/<output>.png`.
+    fn fixture_dir(name: &str, output: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doomfire-capture-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let img = RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+            if (16..48).contains(&x) && (8..28).contains(&y) { Rgb([255, 255, 255]) } else { Rgb([0, 0, 0]) }
+        });
+        img.save(dir.join(format!("{}.png", output))).unwrap();
+        dir
+    }
+
+    fn cold_fire() -> DoomFire {
+        let config = Config {
+            screen_width: Some(WIDTH as usize),
+            screen_height: Some(HEIGHT as usize),
+            scale: Some(1),
+            ..Config::default()
+        };
+        let mut fire = DoomFire::new(&config);
+        fire.clear();
+        fire
+    }
+
+    fn heat_at(fire: &DoomFire, x: usize, y: usize) -> u8 {
+        fire.heat()[y * WIDTH as usize + x]
+    }
+
+    fn screenshot(dir: PathBuf) -> DynamicImage {
+        let mut capturer = make_capturer(CaptureBackend::File, Some(dir)).unwrap();
+        let outputs = [("DP-1".to_string(), true), ("HDMI-A-1".to_string(), false)];
+        let mut shots = take_screenshots_sync(capturer.as_mut(), &outputs, (WIDTH, HEIGHT));
+        // There's no fixture for HDMI-A-1, so only DP-1 comes back
+        assert_eq!(shots.len(), 1);
+        let (name, img) = shots.remove(0);
+        assert_eq!(name, "DP-1");
+        img
+    }
+
+    #[test]
+    fn file_capturer_reads_output_png() {
+        let dir = fixture_dir("read", "DP-1");
+        let mut capturer = FileCapturer { dir: dir.clone() };
+        let img = capturer.capture("DP-1", WIDTH, HEIGHT).unwrap().to_rgb8();
+        assert_eq!(img.dimensions(), (WIDTH, HEIGHT));
+        assert_eq!(img.get_pixel(20, 10), &Rgb([255, 255, 255]));
+        assert_eq!(img.get_pixel(2, 2), &Rgb([0, 0, 0]));
+        assert!(capturer.capture("DP-2", WIDTH, HEIGHT).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn screenshot_burns_into_fire() {
+        let dir = fixture_dir("burn", "DP-1");
+        let img = screenshot(dir.clone());
+        let mut fire = cold_fire();
+        let max_heat = fire.max_heat();
+        // The closed window covered the left half of the screen
+        let rect = CellRect { x: 0, y: 0, width: 32, height: HEIGHT as usize };
+        let mut burn = BurnIn::new(&img, rect, &fire, [0, 0, 0], &BurnStyle::default(), 1);
+        // At 1 FPS the whole region burns in a single frame
+        assert!(!burn.step(&mut fire));
+
+        assert_eq!(heat_at(&fire, 20, 15), max_heat, "white window pixels burn at full heat");
+        assert_eq!(heat_at(&fire, 5, 15), 0, "background pixels stay cold");
+        assert_eq!(heat_at(&fire, 40, 15), 0, "the window's right half was outside the region");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn burn_starts_from_the_bottom() {
+        let dir = fixture_dir("rows", "DP-1");
+        let img = screenshot(dir.clone());
+        let mut fire = cold_fire();
+        let rect = CellRect { x: 16, y: 8, width: 32, height: 20 };
+        let mut burn = BurnIn::new(&img, rect, &fire, [0, 0, 0], &BurnStyle::default(), 20);
+        assert!(burn.step(&mut fire));
+
+        assert_eq!(heat_at(&fire, 30, 27), fire.max_heat());
+        assert_eq!(heat_at(&fire, 30, 8), 0, "the top row hasn't burned yet");
+        let mut frames = 1;
+        while burn.step(&mut fire) {
+            frames += 1;
+        }
+        assert!(frames < 20);
+        assert_eq!(heat_at(&fire, 30, 8), fire.max_heat());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use crate::config::ClockConfig;
+use crate::text::{stamp_text, text_height, text_width, Anchor, Placement};
+use chrono::format::{Item, StrftimeItems};
+use chrono::Local;
+use serde::Deserialize;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// How the clock is drawn.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockStyle {
+    /// Stamped into the heat buffer so the digits burn.
+    #[default]
+    Heat,
+    /// Painted in a flat colour over the fire after palette mapping.
+    Solid,
+}
+
+pub struct Clock {
+    pub style: ClockStyle,
+    pub color: [u8; 3],
+    format: String,
+    anchor: Anchor,
+    offset_x: isize,
+    offset_y: isize,
+    size: usize,
+    heat: f32,
+}
+
+impl Clock {
+    /// Builds a clock from config. Offsets are given in screen pixels and mapped onto the fire grid.
+    pub fn from_config(config: &ClockConfig, scale: usize) -> Self {
+        let scale = scale.max(1) as isize;
+        let mut format = if config.twelve_hour.unwrap_or(false) {
+            "%I:%M".to_string()
+        } else {
+            "%H:%M".to_string()
+        };
+        if config.show_seconds.unwrap_or(false) {
+            format.push_str(":%S");
+        }
+        if config.twelve_hour.unwrap_or(false) {
+            format.push_str(" %p");
+        }
+        if config.show_date.unwrap_or(false) {
+            format.push('\n');
+            let date_format = config.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+            if StrftimeItems::new(date_format).any(|item| item == Item::Error) {
+                eprintln!("[DEBUG] Invalid clock date_format {:?}, using {}", date_format, DEFAULT_DATE_FORMAT);
+                format.push_str(DEFAULT_DATE_FORMAT);
+            } else {
+                format.push_str(date_format);
+            }
+        }
+        Self {
+            style: config.style.unwrap_or_default(),
+            color: config.color.unwrap_or([255, 255, 255]),
+            format,
+            anchor: config.anchor.unwrap_or_default(),
+            offset_x: config.offset_x.unwrap_or(20) as isize / scale,
+            offset_y: config.offset_y.unwrap_or(20) as isize / scale,
+            size: config.size.unwrap_or(2).max(1),
+            heat: config.heat.unwrap_or(1.0).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Returns the current time (and date, if enabled) as it will be drawn.
+    pub fn text(&self) -> String {
+        Local::now().format(&self.format).to_string()
+    }
+
+    /// Draws the current time into a row-major grid, setting lit cells to `value`.
+    pub fn stamp(&self, buffer: &mut [u8], width: usize, height: usize, value: u8) {
+        let text = self.text();
+        let (x, y) = self.anchor.place(
+            text_width(&text, self.size),
+            text_height(&text, self.size),
+            width,
+            height,
+            self.offset_x,
+            self.offset_y,
+        );
+        stamp_text(buffer, width, height, &text, Placement { x, y, size: self.size }, value);
+    }
+
+    /// Returns the heat value the clock burns at for a palette of `palette_len` entries.
+    pub fn heat_value(&self, palette_len: usize) -> u8 {
+        let max_heat = palette_len.saturating_sub(1).min(u8::MAX as usize) as f32;
+        (max_heat * self.heat).round() as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(table: &str) -> Clock {
+        let config: ClockConfig = toml::from_str(table).unwrap();
+        Clock::from_config(&config, 1)
+    }
+
+    #[test]
+    fn invalid_date_format_falls_back_to_the_default() {
+        let clock = clock("show_date = true\ndate_format = \"%Q\"");
+        assert!(clock.format.ends_with(DEFAULT_DATE_FORMAT));
+        // Would panic if the bad specifier had been kept
+        assert_eq!(clock.text().lines().count(), 2);
+    }
+
+    #[test]
+    fn valid_date_format_is_kept() {
+        let clock = clock("show_date = true\ndate_format = \"%d/%m\"");
+        assert!(clock.format.ends_with("\n%d/%m"));
+    }
+}
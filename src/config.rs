@@ -1,3 +1,15 @@
+use crate::audio::AudioInput;
+use crate::burn::HeatMapping;
+use crate::capture::CaptureBackend;
+use crate::clock::ClockStyle;
+use crate::cursor::CursorMode;
+use crate::effect::EffectKind;
+use crate::fire_types::PALETTE_LEN;
+use crate::palette::Dither;
+use crate::reactive::{FireParam, Metric};
+use crate::schedule::ScheduleMode;
+use crate::system_stats::{StatItem, StatsLayout};
+use crate::text::Anchor;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
@@ -7,20 +19,198 @@ pub enum ScreenBurn {
     String(String),
 }
 
+/// A string rendered as a burning heat source, from a `[[text]]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TextConfig {
+    pub text: String,
+    pub x: Option<usize>,
+    pub y: Option<usize>,
+    pub size: Option<usize>,
+    pub heat: Option<f32>,
+}
+
+/// Settings for the clock overlay, from the `[clock]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClockConfig {
+    pub style: Option<ClockStyle>,
+    pub anchor: Option<Anchor>,
+    pub offset_x: Option<i32>,
+    pub offset_y: Option<i32>,
+    pub size: Option<usize>,
+    pub twelve_hour: Option<bool>,
+    pub show_seconds: Option<bool>,
+    pub show_date: Option<bool>,
+    pub date_format: Option<String>,
+    pub color: Option<[u8; 3]>,
+    pub heat: Option<f32>,
+}
+
+/// Settings for the system stats overlay, from the `[stats]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct StatsConfig {
+    pub items: Option<Vec<StatItem>>,
+    pub layout: Option<StatsLayout>,
+    pub anchor: Option<Anchor>,
+    pub offset_x: Option<i32>,
+    pub offset_y: Option<i32>,
+    pub size: Option<usize>,
+    pub refresh_ms: Option<u64>,
+}
+
+/// Maps a system metric onto a fire parameter, from a `[[reactive]]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReactiveConfig {
+    pub metric: Metric,
+    pub param: FireParam,
+    pub from: Option<[f32; 2]>,
+    pub to: Option<[f32; 2]>,
+}
+
+/// Settings for the audio-reactive mode, from the `[audio]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AudioConfig {
+    pub input: Option<AudioInput>,
+    pub path: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub bands: Option<usize>,
+    pub gain: Option<f32>,
+    pub beat_sensitivity: Option<f32>,
+    pub beat_sparks: Option<usize>,
+}
+
+/// Particle spawning overrides, from the `[particles]` table. Unset fields keep the fire type's defaults.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ParticlesConfig {
+    pub enabled: Option<bool>,
+    pub ember_rate: Option<f64>,
+    pub spark_rate: Option<f64>,
+    pub smoke_rate: Option<f64>,
+    pub ash_rate: Option<f64>,
+    pub max_count: Option<usize>,
+    pub life: Option<[u8; 2]>,
+    pub color_range: Option<[u8; 2]>,
+}
+
+/// Settings for the cursor heat source, from the `[cursor]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CursorConfig {
+    pub mode: Option<CursorMode>,
+    pub radius: Option<usize>,
+    pub heat: Option<f32>,
+}
+
+/// Settings for the Matrix rain effect, from the `[matrix]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MatrixConfig {
+    pub speed: Option<f32>,
+    pub trail: Option<f32>,
+    pub density: Option<f32>,
+}
+
+/// Settings for the sine plasma effect, from the `[plasma]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PlasmaConfig {
+    pub speed: Option<f32>,
+    pub scale: Option<f32>,
+}
+
+/// Settings for the lava lamp effect, from the `[lava_lamp]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LavaLampConfig {
+    pub speed: Option<f32>,
+    pub scale: Option<f32>,
+    pub blobs: Option<usize>,
+}
+
+/// Settings for the water ripple effect, from the `[water]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WaterConfig {
+    pub rain: Option<f32>,
+    pub damping: Option<f32>,
+    pub refraction: Option<f32>,
+}
+
+/// Settings for the cellular automaton effect, from the `[life]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LifeConfig {
+    pub rule: Option<String>,
+    pub density: Option<f32>,
+    pub speed: Option<f32>,
+}
+
+/// Settings for the starfield effect, from the `[starfield]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct StarfieldConfig {
+    pub stars: Option<usize>,
+    pub speed: Option<f32>,
+    pub trail: Option<u8>,
+}
+
+/// A fire type switched to at a time of day, from a `[[schedule.times]]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TimeSlotConfig {
+    pub start: String,
+    pub fire_type: String,
+}
+
+/// Settings for changing the fire type over time, from the `[schedule]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScheduleConfig {
+    pub mode: Option<ScheduleMode>,
+    pub interval: Option<f32>,
+    pub fire_types: Option<Vec<String>>,
+    pub times: Option<Vec<TimeSlotConfig>>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+/// How screenshots are turned into heat for screen burn, from the `[burn]` table.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BurnConfig {
+    pub mapping: Option<HeatMapping>,
+    pub gamma: Option<f32>,
+    pub threshold: Option<f32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub screen_width: Option<usize>,
     pub screen_height: Option<usize>,
     pub scale: Option<usize>,
     pub fps: Option<u32>,
+    pub effect: Option<EffectKind>,
     pub fire_type: Option<String>,
     pub background: Option<[u8; 3]>,
+    pub palette_size: Option<usize>,
+    pub dither: Option<Dither>,
+    pub palette_file: Option<String>,
+    pub palette_fade: Option<f32>,
+    pub schedule: Option<ScheduleConfig>,
     pub restart_on_pause: Option<bool>,
     pub pause_on_cover: Option<bool>,
     pub screen_burn: Option<ScreenBurn>,
+    pub capture: Option<CaptureBackend>,
+    pub capture_dir: Option<String>,
+    pub burn: Option<BurnConfig>,
     pub wind_strength: Option<f64>,
     pub show_fps: Option<bool>,
+    pub window_sparks: Option<bool>,
     pub image_path: Option<String>,
+    pub mask_path: Option<String>,
+    pub text: Option<Vec<TextConfig>>,
+    pub clock: Option<ClockConfig>,
+    pub stats: Option<StatsConfig>,
+    pub reactive: Option<Vec<ReactiveConfig>>,
+    pub audio: Option<AudioConfig>,
+    pub particles: Option<ParticlesConfig>,
+    pub cursor: Option<CursorConfig>,
+    pub matrix: Option<MatrixConfig>,
+    pub plasma: Option<PlasmaConfig>,
+    pub lava_lamp: Option<LavaLampConfig>,
+    pub water: Option<WaterConfig>,
+    pub life: Option<LifeConfig>,
+    pub starfield: Option<StarfieldConfig>,
 }
 
 impl Config {
@@ -36,14 +226,38 @@ impl Config {
             screen_height: config.screen_height.or(default.screen_height),
             scale: config.scale.or(default.scale),
             fps: config.fps.or(default.fps),
+            effect: config.effect.or(default.effect),
             fire_type: config.fire_type.or(default.fire_type),
             background: config.background.or(default.background),
+            palette_size: config.palette_size.or(default.palette_size),
+            dither: config.dither.or(default.dither),
+            palette_file: config.palette_file.or(default.palette_file),
+            palette_fade: config.palette_fade.or(default.palette_fade),
+            schedule: config.schedule.or(default.schedule),
             restart_on_pause: config.restart_on_pause.or(default.restart_on_pause),
             pause_on_cover: config.pause_on_cover.or(default.pause_on_cover),
             screen_burn: config.screen_burn.or(default.screen_burn),
+            capture: config.capture.or(default.capture),
+            capture_dir: config.capture_dir.or(default.capture_dir),
+            burn: config.burn.or(default.burn),
             wind_strength: config.wind_strength.or(default.wind_strength),
             show_fps: config.show_fps.or(default.show_fps),
+            window_sparks: config.window_sparks.or(default.window_sparks),
             image_path: config.image_path.or(default.image_path),
+            mask_path: config.mask_path.or(default.mask_path),
+            text: config.text.or(default.text),
+            clock: config.clock.or(default.clock),
+            stats: config.stats.or(default.stats),
+            reactive: config.reactive.or(default.reactive),
+            audio: config.audio.or(default.audio),
+            particles: config.particles.or(default.particles),
+            cursor: config.cursor.or(default.cursor),
+            matrix: config.matrix.or(default.matrix),
+            plasma: config.plasma.or(default.plasma),
+            lava_lamp: config.lava_lamp.or(default.lava_lamp),
+            water: config.water.or(default.water),
+            life: config.life.or(default.life),
+            starfield: config.starfield.or(default.starfield),
         }
     }
 }
@@ -55,14 +269,38 @@ impl Default for Config {
             screen_height: Some(1080),
             scale: Some(4),
             fps: Some(23),
+            effect: Some(EffectKind::Fire),
             fire_type: Some("Original".to_string()),
             background: None,
+            palette_size: Some(PALETTE_LEN),
+            dither: Some(Dither::None),
+            palette_file: None,
+            palette_fade: Some(2.0),
+            schedule: None,
             restart_on_pause: Some(true),
             pause_on_cover: Some(true),
             screen_burn: Some(ScreenBurn::Bool(false)), // Default: disabled
+            capture: Some(CaptureBackend::Auto),
+            capture_dir: None,
+            burn: None,
             wind_strength: Some(0.5),
             show_fps: Some(false),
+            window_sparks: Some(false),
             image_path: None,
+            mask_path: None,
+            text: None,
+            clock: None,
+            stats: None,
+            reactive: None,
+            audio: None,
+            particles: None,
+            cursor: None,
+            matrix: None,
+            plasma: None,
+            lava_lamp: None,
+            water: None,
+            life: None,
+            starfield: None,
         }
     }
 }
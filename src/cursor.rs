@@ -0,0 +1,68 @@
+use crate::config::CursorConfig;
+use crate::effect::Effect;
+use crate::wallpaper::{get_cursor_position, MonitorRect};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// GTK pointer positions newer than this win over polling Hyprland.
+const GTK_POINTER_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// What the cursor does to the fire.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorMode {
+    /// Ignites flames around the cursor.
+    #[default]
+    Torch,
+    /// Extinguishes flames around the cursor.
+    Cold,
+}
+
+/// Pointer position reported by GTK as fractions of the window size, with when it was seen.
+pub type PointerCell = Rc<Cell<Option<(f64, f64, Instant)>>>;
+
+/// Adds or removes heat around the mouse cursor each frame.
+pub struct CursorHeat {
+    mode: CursorMode,
+    radius: usize,
+    strength: f32,
+    /// Set by the window's motion controller when the wallpaper receives pointer events.
+    pub gtk_pointer: PointerCell,
+}
+
+impl CursorHeat {
+    /// Builds the cursor brush from config. The radius is given in screen pixels and mapped onto the fire grid.
+    pub fn from_config(config: &CursorConfig, scale: usize) -> Self {
+        Self {
+            mode: config.mode.unwrap_or_default(),
+            radius: (config.radius.unwrap_or(40) / scale.max(1)).max(1),
+            strength: config.heat.unwrap_or(1.0).clamp(0.0, 1.0),
+            gtk_pointer: Rc::new(Cell::new(None)),
+        }
+    }
+
+    /// Finds the cursor and applies the brush there. `monitors` maps Hyprland's global cursor position onto the fire.
+    pub fn apply(&self, fire: &mut dyn Effect, monitors: &[MonitorRect]) {
+        let Some((nx, ny)) = self.position(monitors) else { return };
+        let x = (nx * fire.width() as f64) as usize;
+        let y = (ny * fire.height() as f64) as usize;
+        let amount = match self.mode {
+            CursorMode::Torch => self.strength,
+            CursorMode::Cold => -self.strength,
+        };
+        fire.apply_heat_brush(x, y, self.radius, amount);
+    }
+
+    /// Returns the cursor as fractions of the output size, preferring fresh GTK pointer events.
+    fn position(&self, monitors: &[MonitorRect]) -> Option<(f64, f64)> {
+        if let Some((nx, ny, seen)) = self.gtk_pointer.get()
+            && seen.elapsed() < GTK_POINTER_TIMEOUT
+        {
+            return Some((nx, ny));
+        }
+        let (x, y) = get_cursor_position()?;
+        monitors.iter().find_map(|m| m.normalize(x, y))
+    }
+}
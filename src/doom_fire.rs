@@ -1,20 +1,26 @@
-use crate::fire_types::{generate_palette, FireType};
-use crate::config::Config;
-use crate::particle::Particle;
+use crate::fire_types::FireType;
+use crate::config::{Config, ParticlesConfig};
+use crate::effect::{brush_cell, for_each_in_disc, Effect, Grid, Reactions};
+use crate::palette::Dither;
+use crate::particle::{Particle, ParticleKind, ParticleProfile};
 use crate::perlin::perlin_noise_1d;
 use rand::rngs::ThreadRng;
 use rand::Rng;
-use strum::IntoEnumIterator;
 
 pub struct DoomFire {
-    pub width: usize,
-    pub height: usize,
-    pub pixel_buffer: Vec<u8>,
-    pub palette: Vec<[u8; 3]>,
-    pub fire_type: FireType,
+    grid: Grid,
+    heat_fraction: Vec<u8>, // Low byte of 16-bit heat, in 1/256ths of a palette step
     t: f64,
     pub particles: Vec<Particle>, // Add this field
+    pub smoke_buffer: Vec<u8>,    // Smoke and ash density, blended over the fire when rendering
+    particle_profile: ParticleProfile,
+    particles_config: Option<ParticlesConfig>, // Reapplied whenever the palette changes
+    frame_time: f64, // Seconds per frame, so particle spawn rates don't depend on FPS
     wind_strength: f64,
+    intensity: f64,
+    gust: f64,
+    whiteness: f32,
+    dither: bool, // Decay by uneven amounts so heat lands between palette entries for the dither to show
     rng: ThreadRng,
 }
 
@@ -22,70 +28,104 @@ impl DoomFire {
     pub fn new(config: &Config) -> Self {
         let width = config.screen_width.unwrap_or(1920) / config.scale.unwrap_or(1);
         let height = config.screen_height.unwrap_or(1080) / config.scale.unwrap_or(1);
-        let fire_type = match config.fire_type.as_deref() {
-            Some("Blue") => FireType::Blue,
-            Some("Rainbow") => FireType::Rainbow,
-            Some("Green") => FireType::Green,
-            Some("Purple") => FireType::Purple,
-            Some("WhiteHot") => FireType::WhiteHot,
-            Some("White") => FireType::White,
-            Some("Ice") => FireType::Ice,
-            Some("Toxic") => FireType::Toxic,
-            Some("FireAndIce") => FireType::FireAndIce, 
-            Some("ChemicalFire") => FireType::ChemicalFire,
-            Some("Cyberpunk") => FireType::Cyberpunk,
-            Some("Aurora") => FireType::Aurora,
-            Some("Plasma") => FireType::Plasma,
-            Some("Void") => FireType::Void,
-            Some("Candy") => FireType::Candy,
-            Some("Random") => {
-                let variants: Vec<FireType> = FireType::iter().collect();
-                let mut rng = ThreadRng::default();
-                let idx = rng.random_range(0..variants.len());
-                println!("Random fire type selected: {:?}", variants[idx]);
-                variants[idx]
-            }
-            _ => FireType::Original,
-        };
-        let background_colour = config.background;
-        let size = width * height;
-        let pixel_buffer = vec![0; size];
-        let palette = generate_palette(fire_type, background_colour, 0.0);
+        let grid = Grid::new(config);
+        let fire_type = grid.palette.fire_type;
 
         let mut doom_fire = Self {
-            width,
-            height,
-            pixel_buffer,
-            palette,
-            fire_type,
+            grid,
+            heat_fraction: Vec::new(),
             t: 0.0,
             particles: Vec::new(),
+            smoke_buffer: Vec::new(),
+            particle_profile: ParticleProfile::new(fire_type, config.particles.as_ref()),
+            particles_config: config.particles.clone(),
+            frame_time: 1.0 / config.fps.unwrap_or(10).max(1) as f64,
             wind_strength: config.wind_strength.unwrap_or(1.0),
+            intensity: 1.0,
+            gust: 0.0,
+            whiteness: 0.0,
+            dither: config.dither.is_some_and(|d| d != Dither::None),
             rng: rand::rng(),
         };
-        doom_fire.initialize_fire();
+        // Allocates the buffers and lights the bottom row
+        doom_fire.resize(width, height);
         doom_fire
     }
+}
+
+impl Effect for DoomFire {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
 
-    pub fn update(&mut self) {
+    fn set_fire_type(&mut self, fire_type: FireType) {
+        self.grid.palette.set_fire_type(fire_type);
+        self.particle_profile = ParticleProfile::new(fire_type, self.particles_config.as_ref());
+        blend_to_white(&mut self.grid.palette.colours, self.whiteness);
+    }
+
+    fn set_custom_palette(&mut self, colours: Vec<[u8; 3]>) {
+        self.grid.palette.set_custom(colours);
+        // The fire type's particle colours mean nothing against imported colours, so use the plain
+        // Original profile: embers drawn from the hottest entry and no themed extras
+        self.particle_profile = ParticleProfile::new(FireType::Original, self.particles_config.as_ref());
+        blend_to_white(&mut self.grid.palette.colours, self.whiteness);
+    }
+
+    /// Also drops whatever fraction of a step the cell had, so the dither doesn't fringe overwritten cells.
+    fn set_heat(&mut self, idx: usize, heat: u8) {
+        self.grid.heat[idx] = heat;
+        self.heat_fraction[idx] = 0;
+    }
+
+    fn heat_fraction(&self) -> Option<&[u8]> {
+        Some(&self.heat_fraction)
+    }
+
+    fn smoke(&self) -> Option<&[u8]> {
+        Some(&self.smoke_buffer)
+    }
+
+    fn update(&mut self) {
         let mut rng = ThreadRng::default();
         self.t += 0.03; // Increase frequency for more rapid wind changes
         let noise_val = perlin_noise_1d(self.t * 1.5);
         let jitter: f64 = rng.random_range(-0.5..=0.5);
-        let wind = ((noise_val + jitter) * self.wind_strength).round() as isize;
-        let delay_chance = 0.3;
-        for y in (2..self.height).rev() {
-            for x in 0..self.width {
-                let src = y * self.width + x;
-                let decay = if rng.random_bool(delay_chance) { 1 } else { 0 }; // Random decay factor
+        let wind_force = (noise_val + jitter) * self.wind_strength + self.gust;
+        self.gust *= 0.9;
+        let wind = wind_force.round() as isize;
+        // Higher intensity means less decay per row, so flames climb higher
+        let delay_chance = (0.3 / self.intensity.max(0.1)).min(1.0);
+        // Decay in 8.8 fixed point, so longer palettes cool by the same share of the palette per row
+        let decay_step = (self.grid.palette.heat_scale() * 256.0).round() as u16;
+        // A whole-step decay leaves no fractions to dither, so dithering spreads it over half to one and a half steps
+        let decay_range = if self.dither { decay_step / 2..=decay_step + decay_step / 2 } else { decay_step..=decay_step };
+        for y in (2..self.grid.height).rev() {
+            for x in 0..self.grid.width {
+                let src = y * self.grid.width + x;
+                // Heat that overlays or burn-in left in a solid cell stays put instead of leaking out
+                if self.grid.solid[src] {
+                    continue;
+                }
+                let decay = rng.random_bool(delay_chance); // Random decay factor
                 let x_offset = rng.random_range(0..3) as i32 as isize - 1 + wind;
                 let dst_x = x as isize + x_offset;
                 let dst_y = if rng.random_bool(0.3) { y - 2 } else { y - 1 };
 
-                if dst_x >= 0 && dst_x < self.width as isize {
-                    let dst = dst_y * self.width + dst_x as usize;
-                    let value = self.pixel_buffer[src].saturating_sub(decay);
-                    self.pixel_buffer[dst] = value;
+                if dst_x >= 0 && dst_x < self.grid.width as isize {
+                    let dst = dst_y * self.grid.width + dst_x as usize;
+                    // Solid cells block heat, including jumps over a one-cell-thick edge
+                    if self.grid.solid[dst] || self.grid.solid[(y - 1) * self.grid.width + dst_x as usize] {
+                        continue;
+                    }
+                    let heat = (self.grid.heat[src] as u16) << 8 | self.heat_fraction[src] as u16;
+                    let value = if decay { heat.saturating_sub(rng.random_range(decay_range.clone())) } else { heat };
+                    self.grid.heat[dst] = (value >> 8) as u8;
+                    self.heat_fraction[dst] = value as u8;
                 }
             }
         }
@@ -93,49 +133,166 @@ impl DoomFire {
         // Spawn new particles randomly at the bottom
         crate::particle::maybe_spawn_particle(
             &mut self.particles,
-            self.fire_type,
-            self.palette.len(),
-            self.width,
-            self.height,
+            &self.particle_profile,
+            self.grid.palette.colours.len(),
+            &self.grid.solid,
+            self.grid.width,
+            self.grid.height,
+            self.frame_time,
         );
 
         // Update and render particles
+        self.smoke_buffer.iter_mut().for_each(|x| *x = 0);
         crate::particle::update_particles(
             &mut self.particles,
-            &mut self.pixel_buffer,
-            self.width,
-            self.height,
+            &mut self.grid.heat,
+            &mut self.heat_fraction,
+            &mut self.smoke_buffer,
+            &self.grid.solid,
+            (self.grid.width, self.grid.height),
+            wind_force as f32,
         );
 
         // Animate Aurora palette by mutating it each frame
-        if self.fire_type == FireType::Aurora {
-            self.palette = generate_palette(self.fire_type, None, self.t as f32);
+        if self.grid.palette.fire_type == FireType::Aurora {
+            self.grid.palette.regenerate(None, self.t as f32);
+            blend_to_white(&mut self.grid.palette.colours, self.whiteness);
         }
     }
 
-    pub fn initialize_fire(&mut self) {
+    fn reset(&mut self) {
         // Clear the pixel buffer
-        self.pixel_buffer.iter_mut().for_each(|x| *x = 0);
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+        self.heat_fraction.iter_mut().for_each(|x| *x = 0);
 
         // Initialize the bottom row
-        for x in 0..self.width {
-            if self.fire_type == FireType::Candy {
-                let rand: usize = self.rng.random_range(self.palette.len() / 2..self.palette.len());
-                self.pixel_buffer[(self.height - 1) * self.width + x] = rand as u8;
+        for x in 0..self.grid.width {
+            let idx = (self.grid.height - 1) * self.grid.width + x;
+            if self.grid.solid[idx] {
+                continue;
+            }
+            if self.grid.palette.fire_type == FireType::Candy {
+                let len = self.grid.palette.colours.len();
+                let rand: usize = self.rng.random_range(len / 2..len);
+                self.set_heat(idx, rand as u8);
             } else {
                 // For other palettes, we start with the last color in the palette
-                self.pixel_buffer[(self.height - 1) * self.width + x] = (self.palette.len() - 1) as u8;
+                self.set_heat(idx, self.max_heat());
             }
         }
 
         self.particles.clear(); // Clear particles on reset
+        self.smoke_buffer.iter_mut().for_each(|x| *x = 0);
+    }
+
+    fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+        if x >= self.grid.width || y >= self.grid.height {
+            return;
+        }
+        let idx = y * self.grid.width + x;
+        if !self.grid.solid[idx] && heat > self.grid.heat[idx] {
+            self.set_heat(idx, heat);
+        }
+    }
+
+    fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+        let max_heat = self.max_heat();
+        for_each_in_disc(self.grid.width, self.grid.height, (x, y), radius, |idx, falloff| {
+            if !self.grid.solid[idx] {
+                let mut heat = self.grid.heat[idx];
+                brush_cell(&mut heat, max_heat, amount, falloff);
+                self.set_heat(idx, heat);
+            }
+        });
+    }
+
+    /// Marks cells as solid so flames flow around them. The mask must be `width * height` long.
+    fn set_mask(&mut self, solid: Vec<bool>) {
+        if self.grid.set_mask(solid) {
+            self.reset();
+        }
     }
 
     // Set the paused screen for the fire - purely background color
-    pub fn pause_fire(&mut self) {
+    fn clear(&mut self) {
         // Clear the pixel buffer
-        self.pixel_buffer.iter_mut().for_each(|x| *x = 0);
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+        self.heat_fraction.iter_mut().for_each(|x| *x = 0);
 
         self.particles.clear(); 
+        self.smoke_buffer.iter_mut().for_each(|x| *x = 0);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.grid.resize(width, height);
+        self.heat_fraction = vec![0; width * height];
+        self.smoke_buffer = vec![0; width * height];
+        self.reset();
+    }
+}
+
+impl Reactions for DoomFire {
+    /// Overwrites the bottom row, which every flame above is fed from. Solid cells stay cold.
+    fn set_source_heat(&mut self, heat: &[u8]) {
+        let row = (self.grid.height - 1) * self.grid.width;
+        for (x, &h) in heat.iter().take(self.grid.width).enumerate() {
+            if !self.grid.solid[row + x] {
+                self.set_heat(row + x, h);
+            }
+        }
+    }
+
+    /// Emits a burst of hot particles around cell (`x`, `y`).
+    fn spawn_burst(&mut self, kind: ParticleKind, x: usize, y: usize, count: usize) {
+        let color_idx = self.max_heat();
+        crate::particle::spawn_burst(
+            &mut self.particles,
+            kind,
+            x.min(self.grid.width - 1),
+            y.min(self.grid.height - 1),
+            count,
+            color_idx,
+            self.grid.width,
+        );
+    }
+
+    /// Adds a burst of wind in a random direction that dies down over a second or so.
+    fn gust(&mut self, strength: f64) {
+        let direction = if self.rng.random_bool(0.5) { 1.0 } else { -1.0 };
+        self.gust = strength * direction;
+    }
+
+    /// Scales flame height; 1.0 is the normal look.
+    fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    fn set_wind_strength(&mut self, wind_strength: f64) {
+        self.wind_strength = wind_strength;
+    }
+
+    /// Shifts the hotter end of the palette towards white; 0.0 is the plain palette.
+    fn set_whiteness(&mut self, whiteness: f32) {
+        let whiteness = whiteness.clamp(0.0, 1.0);
+        if (whiteness - self.whiteness).abs() < 0.005 {
+            return;
+        }
+        self.whiteness = whiteness;
+        self.grid.palette.regenerate(self.grid.palette.background, self.t as f32);
+        blend_to_white(&mut self.grid.palette.colours, whiteness);
+    }
+}
+
+/// Blends each palette entry towards white, weighted by its position so the background stays put.
+fn blend_to_white(palette: &mut [[u8; 3]], whiteness: f32) {
+    if whiteness <= 0.0 || palette.len() < 2 {
+        return;
+    }
+    let last = (palette.len() - 1) as f32;
+    for (i, colour) in palette.iter_mut().enumerate().skip(1) {
+        let blend = whiteness * i as f32 / last;
+        for c in colour.iter_mut() {
+            *c = (*c as f32 + (255.0 - *c as f32) * blend) as u8;
+        }
     }
 }
@@ -0,0 +1,273 @@
+use crate::burn::CellRect;
+use crate::config::Config;
+use crate::doom_fire::DoomFire;
+use crate::fire_types::FireType;
+use crate::life::Life;
+use crate::matrix::MatrixRain;
+use crate::palette::Palette;
+use crate::particle::ParticleKind;
+use crate::plasma::{LavaLamp, Plasma};
+use crate::starfield::Starfield;
+use crate::water::Water;
+use image::DynamicImage;
+use serde::Deserialize;
+
+/// Which animation runs as the wallpaper.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectKind {
+    #[default]
+    Fire,
+    Matrix,
+    Plasma,
+    LavaLamp,
+    Water,
+    Life,
+    Starfield,
+}
+
+/// Builds the effect selected by `effect` in the config.
+pub fn create_effect(config: &Config) -> Box<dyn Effect> {
+    match config.effect.unwrap_or_default() {
+        EffectKind::Fire => Box::new(DoomFire::new(config)),
+        EffectKind::Matrix => Box::new(MatrixRain::new(config)),
+        EffectKind::Plasma => Box::new(Plasma::new(config)),
+        EffectKind::LavaLamp => Box::new(LavaLamp::new(config)),
+        EffectKind::Water => Box::new(Water::new(config)),
+        EffectKind::Life => Box::new(Life::new(config)),
+        EffectKind::Starfield => Box::new(Starfield::new(config)),
+    }
+}
+
+/// The cells every effect draws into: palette index per cell, the palette, and the mask of cells that stay cold.
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub heat: Vec<u8>,
+    pub palette: Palette,
+    pub solid: Vec<bool>,
+}
+
+impl Grid {
+    /// An empty grid with the configured palette; `resize` allocates the cells.
+    pub fn new(config: &Config) -> Self {
+        let fire_type = FireType::from_name(config.fire_type.as_deref());
+        Self {
+            width: 0,
+            height: 0,
+            heat: Vec::new(),
+            palette: Palette::new(fire_type, config),
+            solid: Vec::new(),
+        }
+    }
+
+    /// Reallocates the cells, all cold and unmasked, since the old mask no longer lines up.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.heat = vec![0; width * height];
+        self.solid = vec![false; width * height];
+    }
+
+    /// Replaces the mask, returning false if it's the wrong size for the grid.
+    pub fn set_mask(&mut self, solid: Vec<bool>) -> bool {
+        if solid.len() != self.heat.len() {
+            eprintln!("[DEBUG] Ignoring mask with wrong size: {} cells", solid.len());
+            return false;
+        }
+        self.solid = solid;
+        true
+    }
+
+    /// Zeroes every masked cell, for effects that redraw the whole grid each frame.
+    pub fn blank_solid(&mut self) {
+        for (cell, &solid) in self.heat.iter_mut().zip(&self.solid) {
+            if solid {
+                *cell = 0;
+            }
+        }
+    }
+}
+
+/// A palette-indexed animated wallpaper. The render loop, pausing, screen burn and the overlays only
+/// talk to the running effect through this trait, so any effect gets all of them for free.
+pub trait Effect: Reactions {
+    /// The cells, palette and mask the effect draws into.
+    fn grid(&self) -> &Grid;
+    fn grid_mut(&mut self) -> &mut Grid;
+
+    /// Advances the animation by one frame.
+    fn update(&mut self);
+    /// Starts the animation over, e.g. when unpausing.
+    fn reset(&mut self);
+    /// Blanks the screen to the background colour.
+    fn clear(&mut self);
+    /// Reallocates the grid for a new size, in cells, and starts over.
+    fn resize(&mut self, width: usize, height: usize);
+
+    fn width(&self) -> usize {
+        self.grid().width
+    }
+
+    fn height(&self) -> usize {
+        self.grid().height
+    }
+
+    /// Palette index of every cell, row-major.
+    fn heat(&self) -> &[u8] {
+        &self.grid().heat
+    }
+
+    fn heat_mut(&mut self) -> &mut [u8] {
+        &mut self.grid_mut().heat
+    }
+
+    /// Sets a cell to a whole palette index.
+    fn set_heat(&mut self, idx: usize, heat: u8) {
+        self.heat_mut()[idx] = heat;
+    }
+
+    /// Burns an overlay into the grid. `draw` marks cells with any non-zero value in a blank
+    /// `width * height` mask, and every marked cell is set to `heat`.
+    fn stamp_overlay(&mut self, heat: u8, draw: &dyn Fn(&mut [u8], usize, usize)) {
+        let (width, height) = (self.width(), self.height());
+        let mut mask = vec![0; width * height];
+        draw(&mut mask, width, height);
+        for (idx, _) in mask.iter().enumerate().filter(|(_, m)| **m != 0) {
+            self.set_heat(idx, heat);
+        }
+    }
+
+    fn palette(&self) -> &[[u8; 3]] {
+        &self.grid().palette.colours
+    }
+
+    fn fire_type(&self) -> FireType {
+        self.grid().palette.fire_type
+    }
+
+    /// Switches to another fire type's palette, keeping the current palette length.
+    fn set_fire_type(&mut self, fire_type: FireType) {
+        self.grid_mut().palette.set_fire_type(fire_type);
+    }
+
+    /// Replaces the palette with an imported one, until the next fire type switch.
+    fn set_custom_palette(&mut self, colours: Vec<[u8; 3]>) {
+        self.grid_mut().palette.set_custom(colours);
+    }
+
+    /// Whether the cell at `idx` is masked off and must stay cold.
+    fn is_solid(&self, idx: usize) -> bool {
+        self.grid().solid[idx]
+    }
+
+    /// Marks cells as solid. The mask must be `width * height` long.
+    fn set_mask(&mut self, solid: Vec<bool>) {
+        self.grid_mut().set_mask(solid);
+    }
+
+    /// The fraction of a palette step each cell's heat sits above `heat()`, 0 - 255, for effects with 16-bit heat.
+    fn heat_fraction(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// Smoke density per cell, blended over the palette colour when rendering.
+    fn smoke(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// Colour per cell with an opacity in the fourth channel, drawn over the palette colour when rendering.
+    fn image_layer(&self) -> Option<&[[u8; 4]]> {
+        None
+    }
+
+    /// The hottest palette index.
+    fn max_heat(&self) -> u8 {
+        self.palette().len().saturating_sub(1).min(u8::MAX as usize) as u8
+    }
+
+    /// Raises a cell to at least `heat`. Cells outside the grid or solid cells are left alone.
+    fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        let idx = y * self.width() + x;
+        if !self.is_solid(idx) {
+            let cell = &mut self.heat_mut()[idx];
+            *cell = (*cell).max(heat);
+        }
+    }
+
+    /// Heats (positive `amount`) or cools (negative `amount`) a disc around (`x`, `y`), strongest at the centre.
+    /// `amount` is a fraction of the hottest palette entry.
+    fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+        let max_heat = self.max_heat();
+        let (width, height) = (self.width(), self.height());
+        for_each_in_disc(width, height, (x, y), radius, |idx, falloff| {
+            if !self.is_solid(idx) {
+                brush_cell(&mut self.heat_mut()[idx], max_heat, amount, falloff);
+            }
+        });
+    }
+}
+
+/// How an effect responds to the reactive features: window events, audio, system load and the like.
+/// Every hook does nothing by default, so effects only implement the ones that mean something to them.
+pub trait Reactions {
+    /// Lets the effect use the screenshot under `rect` itself instead of having it burned in.
+    /// Returns false to burn it in as usual.
+    fn show_screenshot(&mut self, _screenshot: &DynamicImage, _rect: CellRect) -> bool {
+        false
+    }
+    /// Overwrites whatever feeds the effect from the bottom row.
+    fn set_source_heat(&mut self, _heat: &[u8]) {}
+    /// Emits a burst of particles around cell (`x`, `y`).
+    fn spawn_burst(&mut self, _kind: ParticleKind, _x: usize, _y: usize, _count: usize) {}
+    /// Adds a burst of wind that dies down over a second or so.
+    fn gust(&mut self, _strength: f64) {}
+    /// Scales how lively the effect is; 1.0 is the normal look.
+    fn set_intensity(&mut self, _intensity: f64) {}
+    fn set_wind_strength(&mut self, _wind_strength: f64) {}
+    /// Shifts the hotter end of the palette towards white; 0.0 is the plain palette.
+    fn set_whiteness(&mut self, _whiteness: f32) {}
+}
+
+/// Blends an `image_layer` pixel over `base` by its opacity.
+pub fn blend_image(base: [u8; 3], pixel: [u8; 4]) -> [u8; 3] {
+    let alpha = pixel[3] as u32;
+    let mix = |b: u8, p: u8| ((b as u32 * (255 - alpha) + p as u32 * alpha) / 255) as u8;
+    [mix(base[0], pixel[0]), mix(base[1], pixel[1]), mix(base[2], pixel[2])]
+}
+
+/// Calls `f` with the index and falloff (1.0 at the centre) of every grid cell within `radius` of (`x`, `y`).
+pub fn for_each_in_disc(
+    width: usize,
+    height: usize,
+    (x, y): (usize, usize),
+    radius: usize,
+    mut f: impl FnMut(usize, f32),
+) {
+    let r = radius as isize;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let (cx, cy) = (x as isize + dx, y as isize + dy);
+            if cx < 0 || cy < 0 || cx >= width as isize || cy >= height as isize {
+                continue;
+            }
+            let distance = ((dx * dx + dy * dy) as f32).sqrt();
+            if distance > radius as f32 {
+                continue;
+            }
+            f(cy as usize * width + cx as usize, 1.0 - distance / (radius as f32 + 1.0));
+        }
+    }
+}
+
+/// Heats a cell to at least `amount * falloff` of `max_heat`, or cools it by that fraction when `amount` is negative.
+pub fn brush_cell(cell: &mut u8, max_heat: u8, amount: f32, falloff: f32) {
+    if amount >= 0.0 {
+        *cell = (*cell).max((max_heat as f32 * amount * falloff) as u8);
+    } else {
+        *cell = (*cell as f32 * (1.0 + amount * falloff)) as u8;
+    }
+}
@@ -1,3 +1,6 @@
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 #[allow(dead_code)]
@@ -21,6 +24,32 @@ pub enum FireType {
     Candy,
 }
 
+impl FireType {
+    /// Parses the `fire_type` config value, picking a random type for "Random" and Original for anything unknown.
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("Random") => {
+                let variants: Vec<FireType> = FireType::iter().collect();
+                let mut rng = ThreadRng::default();
+                let idx = rng.random_range(0..variants.len());
+                println!("Random fire type selected: {:?}", variants[idx]);
+                variants[idx]
+            }
+            Some(name) => FireType::parse(name).unwrap_or(FireType::Original),
+            None => FireType::Original,
+        }
+    }
+
+    /// Looks up a fire type by its exact name, e.g. "FireAndIce".
+    pub fn parse(name: &str) -> Option<Self> {
+        FireType::iter().find(|t| format!("{:?}", t) == name)
+    }
+}
+
+/// Entries in each built-in palette as generated, and the default `palette_size`. Palettes are resampled to
+/// `palette_size` (2 - 256 entries), so heat runs from 0 to `palette_size - 1`.
+pub const PALETTE_LEN: usize = 37;
+
 pub fn generate_palette(fire_type: FireType, background_colour: Option<[u8; 3]>, phase: f32) -> Vec<[u8; 3]> {
     let mut pal = match fire_type {
         FireType::FireAndIce => (0..=36)
@@ -0,0 +1,278 @@
+use crate::config::Config;
+use crate::effect::{for_each_in_disc, Effect, Grid, Reactions};
+use crate::particle::ParticleKind;
+use anyhow::{bail, Context, Result};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+/// Below this fraction of live cells the board is reseeded, so it doesn't settle into a blank screen.
+const MIN_POPULATION: f32 = 0.001;
+/// Palette steps a dead cell's glow drops each frame.
+const TRAIL_DECAY: u8 = 2;
+
+/// A birth/survival rule in B/S notation, with an optional number of states for "Generations" rules
+/// where dying cells fade out over several steps, e.g. Brian's Brain is `B2/S/C3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    birth: [bool; 9],
+    survive: [bool; 9],
+    states: u8,
+}
+
+impl Rule {
+    /// Accepts "life", "highlife", "brians_brain" or a rule string like `B3/S23` or `B2/S/C3`.
+    pub fn parse(rule: &str) -> Result<Self> {
+        let rule = match rule.to_ascii_lowercase().as_str() {
+            "life" => "B3/S23".to_string(),
+            "highlife" => "B36/S23".to_string(),
+            "brians_brain" => "B2/S/C3".to_string(),
+            _ => rule.to_string(),
+        };
+        let mut parsed = Self { birth: [false; 9], survive: [false; 9], states: 2 };
+        for part in rule.split('/') {
+            let part = part.trim();
+            let mut chars = part.chars();
+            let kind = chars.next().map(|c| c.to_ascii_uppercase());
+            let digits = chars.as_str();
+            match kind {
+                Some('B') | Some('S') => {
+                    let set = if kind == Some('B') { &mut parsed.birth } else { &mut parsed.survive };
+                    for c in digits.chars() {
+                        let n = c.to_digit(10).filter(|&n| n <= 8).with_context(|| format!("Bad neighbour count '{}' in rule {}", c, rule))?;
+                        set[n as usize] = true;
+                    }
+                }
+                Some('C') => {
+                    parsed.states = digits.parse::<u8>().with_context(|| format!("Bad state count in rule {}", rule))?.max(2);
+                }
+                _ => bail!("Can't parse rule {}", rule),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// A cellular automaton, seeded from burned-in screenshots: whatever would have burned comes alive instead.
+/// Live cells start at the hot end of the palette and cool as they age.
+pub struct Life {
+    grid: Grid,
+    state: Vec<u8>, // 0 dead, 1 alive, 2.. dying in Generations rules
+    next: Vec<u8>,
+    age: Vec<u16>,  // Generations each live cell has survived
+    rule: Rule,
+    density: f32,
+    generations_per_second: f32,
+    step_due: f32, // Fractional generations carried between frames
+    intensity: f32,
+    frame_time: f32,
+    rng: ThreadRng,
+}
+
+impl Life {
+    pub fn new(config: &Config) -> Self {
+        let scale = config.scale.unwrap_or(1).max(1);
+        let life = config.life.as_ref();
+        let rule = life.and_then(|l| l.rule.as_deref()).unwrap_or("life");
+        let rule = Rule::parse(rule).unwrap_or_else(|e| {
+            eprintln!("{:#}; using B3/S23", e);
+            Rule::parse("life").unwrap()
+        });
+        let mut board = Self {
+            grid: Grid::new(config),
+            state: Vec::new(),
+            next: Vec::new(),
+            age: Vec::new(),
+            rule,
+            density: life.and_then(|l| l.density).unwrap_or(0.2).clamp(0.0, 1.0),
+            generations_per_second: life.and_then(|l| l.speed).unwrap_or(10.0).max(0.1),
+            step_due: 0.0,
+            intensity: 1.0,
+            frame_time: 1.0 / config.fps.unwrap_or(10).max(1) as f32,
+            rng: rand::rng(),
+        };
+        board.resize(
+            config.screen_width.unwrap_or(1920) / scale,
+            config.screen_height.unwrap_or(1080) / scale,
+        );
+        board
+    }
+
+    fn set_alive(&mut self, idx: usize, alive: bool) {
+        if self.grid.solid[idx] {
+            return;
+        }
+        if alive && self.state[idx] != 1 {
+            self.state[idx] = 1;
+            self.age[idx] = 0;
+        } else if !alive {
+            self.state[idx] = 0;
+        }
+    }
+
+    /// Fills the board with random live cells at the configured density.
+    fn seed(&mut self) {
+        for idx in 0..self.state.len() {
+            let alive = self.rng.random_bool(self.density as f64);
+            self.set_alive(idx, alive);
+        }
+    }
+
+    /// Computes one generation, wrapping around the edges.
+    fn step(&mut self) {
+        let (w, h) = (self.grid.width, self.grid.height);
+        let mut population = 0;
+        for y in 0..h {
+            let (up, down) = ((y + h - 1) % h, (y + 1) % h);
+            for x in 0..w {
+                let (left, right) = ((x + w - 1) % w, (x + 1) % w);
+                let i = y * w + x;
+                if self.grid.solid[i] {
+                    self.next[i] = 0;
+                    continue;
+                }
+                let neighbours = [
+                    up * w + left, up * w + x, up * w + right,
+                    y * w + left, y * w + right,
+                    down * w + left, down * w + x, down * w + right,
+                ]
+                .iter()
+                .filter(|&&n| self.state[n] == 1)
+                .count();
+                let state = self.state[i];
+                self.next[i] = match state {
+                    0 if self.rule.birth[neighbours] => 1,
+                    0 => 0,
+                    1 if self.rule.survive[neighbours] => 1,
+                    // Dying cells count down through the extra states before they're dead
+                    s if s + 1 < self.rule.states => s + 1,
+                    _ => 0,
+                };
+                if self.next[i] == 1 {
+                    population += 1;
+                    self.age[i] = if state == 1 { self.age[i].saturating_add(1) } else { 0 };
+                }
+            }
+        }
+        std::mem::swap(&mut self.state, &mut self.next);
+        if (population as f32) < self.state.len() as f32 * MIN_POPULATION {
+            self.seed();
+        }
+    }
+
+    /// Maps live cells by age and dying cells by state onto the palette; dead cells keep a fading glow.
+    fn render(&mut self) {
+        let max_heat = self.grid.palette.colours.len().saturating_sub(1) as u8;
+        let floor = max_heat / 3;
+        let states = self.rule.states.max(2);
+        let scale = self.grid.palette.heat_scale();
+        let trail_decay = (TRAIL_DECAY as f32 * scale).round() as u8;
+        for i in 0..self.grid.heat.len() {
+            let value = match self.state[i] {
+                0 => self.grid.heat[i].saturating_sub(trail_decay),
+                1 => max_heat.saturating_sub((self.age[i] as f32 * scale).min(u8::MAX as f32) as u8).max(floor),
+                s => (floor as u32 * (states - s) as u32 / states as u32) as u8,
+            };
+            self.grid.heat[i] = value;
+        }
+    }
+}
+
+impl Effect for Life {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        self.step_due += self.generations_per_second * self.frame_time * self.intensity;
+        while self.step_due >= 1.0 {
+            self.step_due -= 1.0;
+            self.step();
+        }
+        self.render();
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+        self.seed();
+    }
+
+    fn clear(&mut self) {
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+        self.state.iter_mut().for_each(|x| *x = 0);
+        self.age.iter_mut().for_each(|x| *x = 0);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        let size = width * height;
+        self.grid.resize(width, height);
+        self.state = vec![0; size];
+        self.next = vec![0; size];
+        self.age = vec![0; size];
+        self.reset();
+    }
+
+    fn set_mask(&mut self, solid: Vec<bool>) {
+        if self.grid.set_mask(solid) {
+            self.reset();
+        }
+    }
+
+    /// Burned-in cells hot enough to ignite come alive, so a closed window turns into living cells.
+    fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+        if x < self.grid.width && y < self.grid.height && heat > self.max_heat() / 2 {
+            self.set_alive(y * self.grid.width + x, true);
+        }
+    }
+
+    /// Torch sprinkles live cells around the cursor; cold kills them.
+    fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+        let (width, height) = (self.grid.width, self.grid.height);
+        let mut cells = Vec::new();
+        for_each_in_disc(width, height, (x, y), radius, |idx, falloff| cells.push((idx, falloff)));
+        for (idx, falloff) in cells {
+            if amount >= 0.0 {
+                if self.rng.random_bool((amount * falloff * 0.3).clamp(0.0, 1.0) as f64) {
+                    self.set_alive(idx, true);
+                }
+            } else if self.rng.random_bool((-amount * falloff).clamp(0.0, 1.0) as f64) {
+                self.set_alive(idx, false);
+            }
+        }
+    }
+}
+
+impl Reactions for Life {
+    /// Hot spots along the bottom row come alive.
+    fn set_source_heat(&mut self, heat: &[u8]) {
+        if self.grid.height == 0 {
+            return;
+        }
+        let threshold = self.max_heat() / 2;
+        let row = (self.grid.height - 1) * self.grid.width;
+        for (x, &h) in heat.iter().take(self.grid.width).enumerate() {
+            if h > threshold {
+                self.set_alive(row + x, true);
+            }
+        }
+    }
+
+    /// Drops a small patch of random cells around (`x`, `y`).
+    fn spawn_burst(&mut self, _kind: ParticleKind, x: usize, y: usize, count: usize) {
+        let radius = (count / 2).clamp(2, 8);
+        let (width, height) = (self.grid.width, self.grid.height);
+        let mut cells = Vec::new();
+        for_each_in_disc(width, height, (x, y), radius, |idx, _| cells.push(idx));
+        for idx in cells {
+            let alive = self.rng.random_bool(0.5);
+            self.set_alive(idx, alive);
+        }
+    }
+
+    fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity.max(0.0) as f32;
+    }
+}
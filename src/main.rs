@@ -1,23 +1,60 @@
+mod audio;
+mod burn;
+mod capture;
+mod clock;
+mod cursor;
 mod doom_fire;
+mod effect;
+mod life;
+mod mask;
+mod matrix;
+mod palette;
+mod palette_import;
 mod perlin;
+mod plasma;
+mod reactive;
+mod schedule;
+mod starfield;
+mod system_stats;
+mod text;
 mod wallpaper;
+mod water;
+mod window_edges;
+mod window_sparks;
 
 pub mod config;
 pub mod fire_types;
 pub mod particle;
 
+use crate::audio::{bands_to_row, open_source, spawn_audio_thread, AudioFrame};
+use crate::burn::{BurnIn, BurnStyle, CellRect};
+use crate::capture::{make_capturer, take_screenshots_sync, Capturer};
+use crate::clock::{Clock, ClockStyle};
 use crate::config::{Config, ScreenBurn};
-use crate::doom_fire::DoomFire;
-use crate::wallpaper::{get_outputs_covered, is_system_sleeping};
+use crate::cursor::CursorHeat;
+use crate::effect::{blend_image, create_effect, Effect, EffectKind};
+use crate::fire_types::FireType;
+use crate::mask::load_mask;
+use crate::palette::{Dither, PaletteFade};
+use crate::palette_import::PaletteFile;
+use crate::particle::{blend_smoke, ParticleKind};
+use crate::reactive::Reactive;
+use crate::schedule::{following, Schedule};
+use crate::system_stats::StatsOverlay;
+use crate::text::{stamp_text, text_width, HeatText, Placement};
+use crate::wallpaper::{get_outputs_covered, ClientRect, is_system_sleeping, query_hypr_state, spawn_event_listener};
+use crate::window_edges::burn_window_edges;
+use crate::window_sparks::WindowSparks;
 use anyhow::{Context, Result};
 use gtk4 as gtk;
 use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
 use gtk::glib::source::timeout_add_local;
+use gtk::glib::unix_signal_add_local;
 use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, Picture};
-use image::{DynamicImage, GenericImageView};
+use gtk::{Application, ApplicationWindow, EventControllerMotion, Picture};
+use image::DynamicImage;
 use rayon::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
@@ -25,21 +62,11 @@ use std::time::{Duration, Instant};
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::mpsc; // added
+use std::sync::{Arc, Mutex};
+use strum::IntoEnumIterator;
 
 const BYTES_PER_PIXEL: usize = 3; // RGB = 3 bytes
-
-const FONT: [[[u8; 3]; 5]; 10] = [
-    [[1, 1, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 1, 1]], // 0
-    [[0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0]], // 1
-    [[1, 1, 1], [0, 0, 1], [1, 1, 1], [1, 0, 0], [1, 1, 1]], // 2
-    [[1, 1, 1], [0, 0, 1], [1, 1, 1], [0, 0, 1], [1, 1, 1]], // 3
-    [[1, 0, 1], [1, 0, 1], [1, 1, 1], [0, 0, 1], [0, 0, 1]], // 4
-    [[1, 1, 1], [1, 0, 0], [1, 1, 1], [0, 0, 1], [1, 1, 1]], // 5
-    [[1, 1, 1], [1, 0, 0], [1, 1, 1], [1, 0, 1], [1, 1, 1]], // 6
-    [[1, 1, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1]], // 7
-    [[1, 1, 1], [1, 0, 1], [1, 1, 1], [1, 0, 1], [1, 1, 1]], // 8
-    [[1, 1, 1], [1, 0, 1], [1, 1, 1], [0, 0, 1], [1, 1, 1]], // 9
-];
+const SIGUSR1: i32 = 10;
 
 fn main() -> Result<()> {
     run_wallpaper()
@@ -75,13 +102,92 @@ screen_height = 1080
 scale = 4
 fps = 24
 fire_type = "Original"
+# effect = "fire" # fire, matrix, plasma, lava_lamp, water, life or starfield
 # background = [0, 0, 0]
+# palette_size = 37 # 2 - 256; higher is smoother
+# dither = "none" # none, ordered or blue_noise
+# palette_file = "pywal" # .gpl, .ggr, pywal colors.json or an image; replaces the fire_type palette
+# palette_fade = 2.0 # seconds; `pkill -USR1 doomfire-wallpaper` switches to the next fire type
 # restart_on_pause = true
 # pause_on_cover = true
-# screen_burn = false # true, false, or "image"
+# screen_burn = false # true, false, "image" or "edges"
+# capture = "auto" # "auto", "screencopy", "grim" or "file"
 # image_path = "/path/to/image.png"
+# mask_path = "/path/to/mask.png"
 # wind_strength = 0.5
 # show_fps = false
+# window_sparks = false
+#
+# [[text]]
+# text = "{hostname}"
+# x = 100
+# y = 100
+# size = 2
+# heat = 1.0
+#
+# [clock]
+# style = "heat" # "heat" burns the digits, "solid" draws them in `color` over the fire
+# anchor = "top-right"
+# offset_x = 20
+# offset_y = 20
+# size = 2
+# twelve_hour = false
+# show_seconds = false
+# show_date = false
+# color = [255, 255, 255]
+#
+# [stats]
+# items = ["cpu", "mem", "temp"]
+# layout = "vertical"
+# anchor = "top-left"
+# refresh_ms = 1000
+#
+# [[reactive]]
+# metric = "cpu" # cpu, mem, temp, net or disk
+# param = "intensity" # intensity, wind or whiteness
+#
+# [audio]
+# input = "monitor" # monitor, stdin or wav
+# bands = 16
+#
+# [particles]
+# enabled = true
+# ember_rate = 7.2 # per 1000 columns per second
+# spark_rate = 2.4 # sparks, smoke and ash default per fire type
+# max_count = 2000
+#
+# [cursor]
+# mode = "torch" # torch or cold
+# radius = 40
+#
+# [matrix]
+# speed = 12 # glyph rows per second
+# trail = 12
+#
+# [lava_lamp]
+# blobs = 8
+# speed = 1.0
+#
+# [water]
+# rain = 3.0 # drops per second
+#
+# [life]
+# rule = "life" # life, highlife, brians_brain or e.g. "B3/S23"
+#
+# [starfield]
+# stars = 800
+#
+# [schedule]
+# mode = "rotate" # rotate, shuffle or time_of_day
+# interval = 10.0 # minutes
+# fire_types = ["Original", "Blue", "Aurora"]
+# [[schedule.times]]
+# start = "sunset" # HH:MM, sunrise or sunset (needs latitude and longitude)
+# fire_type = "Void"
+#
+# [burn]
+# mapping = "color_distance" # color_distance, luminance or edges
+# gamma = 1.0
 "#;
 
 /// Runs the GTK application and the wallpaper animation loop.
@@ -99,7 +205,29 @@ fn build_ui(app: &Application) {
     println!("App Connected!");
     let config = Config::load();
     println!("Using config: {:?}", config);
-    let fire = Rc::new(RefCell::new(DoomFire::new(&config)));
+    let fire: Rc<RefCell<Box<dyn Effect>>> = Rc::new(RefCell::new(create_effect(&config)));
+
+    // Load the obstacle mask if provided; flames flow around its solid cells
+    if let Some(path_str) = &config.mask_path {
+        let mut fire = fire.borrow_mut();
+        let path = resolve_path(path_str);
+        match load_mask(&path, fire.width(), fire.height()) {
+            Ok(mask) => fire.set_mask(mask),
+            Err(e) => eprintln!("{:#}", e),
+        }
+    }
+
+    // Import a palette to use instead of the fire type's; "pywal" follows the current wal theme
+    let mut palette_file = config.palette_file.as_deref().map(|path_str| {
+        let path = resolve_path(if path_str == "pywal" { "~/.cache/wal/colors.json" } else { path_str });
+        PaletteFile::new(path, config.background, fire.borrow().palette().len())
+    });
+    if let Some(palette_file) = &mut palette_file {
+        match palette_file.load() {
+            Ok(colours) => fire.borrow_mut().set_custom_palette(colours),
+            Err(e) => eprintln!("{:#}", e),
+        }
+    }
 
     let restart_on_pause = config.restart_on_pause.unwrap_or(false);
     let fps = config.fps.unwrap_or(10);
@@ -109,6 +237,42 @@ fn build_ui(app: &Application) {
     let height = config.screen_height.unwrap();
     let width = config.screen_width.unwrap();
     let scale = config.scale.unwrap_or(1);
+    let heat_texts: Vec<HeatText> = config
+        .text
+        .iter()
+        .flatten()
+        .map(|t| HeatText::from_config(t, scale))
+        .collect();
+    let clock = config.clock.as_ref().map(|c| Clock::from_config(c, scale));
+    let mut stats_overlay = config.stats.as_ref().map(|s| StatsOverlay::from_config(s, scale));
+    let wind_strength = config.wind_strength.unwrap_or(1.0);
+    let mut reactive = config.reactive.as_deref().map(|r| Reactive::from_config(r, wind_strength));
+
+    // Start the audio thread if the visualizer mode is on
+    let audio_rx = config.audio.as_ref().and_then(|audio| {
+        let path = audio.path.as_deref().map(resolve_path);
+        match open_source(audio, path.as_deref()) {
+            Ok(source) => Some(spawn_audio_thread(source, audio)),
+            Err(e) => {
+                eprintln!("Failed to open audio source: {:#}", e);
+                None
+            }
+        }
+    });
+    let beat_sparks = config.audio.as_ref().and_then(|a| a.beat_sparks).unwrap_or(12);
+    let mut audio_frame = AudioFrame::default();
+
+    // Listen for window events if reactive sparks are on
+    let compositor_events = if config.window_sparks.unwrap_or(false) {
+        let rx = spawn_event_listener();
+        if rx.is_none() {
+            eprintln!("Could not connect to the Hyprland event socket; window sparks are disabled");
+        }
+        rx
+    } else {
+        None
+    };
+    let mut window_sparks = WindowSparks::new(scale);
 
     let window = ApplicationWindow::builder()
         .application(app)
@@ -119,7 +283,24 @@ fn build_ui(app: &Application) {
     window.set_child(Some(&picture));
     window.present();
 
+    // The cursor can act as a heat source; GTK pointer events are used when the wallpaper gets input
+    let cursor_heat = config.cursor.as_ref().map(|c| CursorHeat::from_config(c, scale));
+    if let Some(cursor_heat) = &cursor_heat {
+        let motion = EventControllerMotion::new();
+        let pointer = cursor_heat.gtk_pointer.clone();
+        let widget = picture.clone();
+        motion.connect_motion(move |_, x, y| {
+            let (w, h) = (widget.width().max(1) as f64, widget.height().max(1) as f64);
+            pointer.set(Some((x / w, y / h, Instant::now())));
+        });
+        let pointer = cursor_heat.gtk_pointer.clone();
+        motion.connect_leave(move |_| pointer.set(None));
+        picture.add_controller(motion);
+    }
+
     let mut last_screenshot: HashMap<String, DynamicImage> = HashMap::new();
+    let mut prev_clients: Vec<ClientRect> = Vec::new();
+    let mut burns: Vec<BurnIn> = Vec::new();
     let mut was_paused = false;
     let mut last_screenshot_time = Instant::now();
     let mut frame_count = 0;
@@ -128,10 +309,24 @@ fn build_ui(app: &Application) {
 
     // Channel to receive screenshots taken on a background thread without blocking the UI
     let (screenshot_tx, screenshot_rx) = mpsc::channel::<Vec<(String, DynamicImage)>>();
+    // Shared with the background thread so the Wayland connection is reused between screenshots
+    let capturer: Option<Arc<Mutex<Box<dyn Capturer>>>> = match make_capturer(
+        config.capture.unwrap_or_default(),
+        config.capture_dir.as_deref().map(resolve_path),
+    ) {
+        Ok(capturer) => Some(Arc::new(Mutex::new(capturer))),
+        Err(e) => {
+            eprintln!("Screenshots for screen burn are disabled: {:#}", e);
+            None
+        }
+    };
+    let capture_size = (fire.borrow().width() as u32, fire.borrow().height() as u32);
     
     let background_color = config.background.unwrap_or([0, 0, 0]);
     // Create the pixel buffer once and reuse it to avoid re-allocation on every frame.
     let mut pixels = vec![0u8; width * height * BYTES_PER_PIXEL];
+    // Cells covered by solid-colour overlays, drawn over the fire after palette mapping.
+    let mut overlay_mask: Vec<u8> = Vec::new();
 
     // Load background image if provided
     let bg_image = if let Some(path_str) = &config.image_path {
@@ -152,11 +347,36 @@ fn build_ui(app: &Application) {
 
     let use_static_burn_image = matches!(screen_burn_config, ScreenBurn::String(ref s) if s == "image");
     let do_screen_burn = use_static_burn_image || matches!(screen_burn_config, ScreenBurn::Bool(true));
+    let burn_window_borders = matches!(screen_burn_config, ScreenBurn::String(ref s) if s == "edges");
     let show_bg_image = !do_screen_burn && bg_image.is_some();
+    let burn_style = BurnStyle::from_config(config.burn.as_ref());
+    let dither = config.dither.unwrap_or_default();
+    if dither != Dither::None && config.effect.unwrap_or_default() != EffectKind::Fire {
+        eprintln!("[DEBUG] dither only applies to the fire effect, ignoring it");
+    }
+    let mut palette_fade = PaletteFade::new(Duration::from_secs_f32(config.palette_fade.unwrap_or(2.0).max(0.0)));
+
+    let mut schedule = config.schedule.as_ref().and_then(|s| {
+        Schedule::from_config(s)
+            .map_err(|e| eprintln!("Ignoring [schedule]: {:#}", e))
+            .ok()
+    });
+    let all_fire_types: Vec<FireType> = FireType::iter().collect();
+
+    // `pkill -USR1 doomfire-wallpaper` switches to the next fire type
+    let next_fire_type = Rc::new(Cell::new(false));
+    unix_signal_add_local(SIGUSR1, {
+        let next_fire_type = next_fire_type.clone();
+        move || {
+            next_fire_type.set(true);
+            gtk::glib::ControlFlow::Continue
+        }
+    });
 
     timeout_add_local(std::time::Duration::from_millis(1000 / fps as u64), {
         move || {
             let mut fire = fire.borrow_mut(); // Now only borrows mutably when needed.
+            let fire = fire.as_mut();
 
             if show_fps {
                 frame_count += 1;
@@ -167,6 +387,25 @@ fn build_ui(app: &Application) {
                 }
             }
 
+            let current_fire_type = fire.fire_type();
+            let switch_to = if next_fire_type.replace(false) {
+                Some(match schedule.as_mut() {
+                    Some(schedule) => schedule.next(current_fire_type),
+                    None => following(&all_fire_types, current_fire_type),
+                })
+            } else {
+                schedule.as_mut().and_then(|s| s.poll(current_fire_type))
+            };
+            if let Some(next) = switch_to {
+                eprintln!("[DEBUG] Switching fire type to {:?}", next);
+                palette_fade.start();
+                fire.set_fire_type(next);
+            }
+            if let Some(colours) = palette_file.as_mut().and_then(|f| f.poll()) {
+                palette_fade.start();
+                fire.set_custom_palette(colours);
+            }
+
             // Drain any screenshots that were produced by background threads
             while let Ok(batch) = screenshot_rx.try_recv() {
                 for (name, img) in batch {
@@ -174,10 +413,19 @@ fn build_ui(app: &Application) {
                 }
             }
 
-            let covered_outputs = get_outputs_covered();
+            let hypr_state = query_hypr_state();
+            // Windows that were visible last frame but aren't now, for limiting burn-in to their area
+            let vanished_clients: Vec<ClientRect> = prev_clients
+                .iter()
+                .filter(|c| !hypr_state.clients.iter().any(|n| n.address == c.address))
+                .cloned()
+                .collect();
+            prev_clients = hypr_state.clients.clone();
+            let covered_outputs = hypr_state.outputs;
             let all_covered = covered_outputs.iter().all(|(_, c)| *c);
             let sleeping = is_system_sleeping();
-            let paused = (pause_on_cover && all_covered) || sleeping;
+            // Window edges only burn while windows are showing, so covering the screen must not pause them
+            let paused = (pause_on_cover && all_covered && !burn_window_borders) || sleeping;
 
             if use_static_burn_image {
                 if let Some(img) = &bg_image {
@@ -196,21 +444,24 @@ fn build_ui(app: &Application) {
                 if just_paused {
                     if restart_on_pause {
                         eprintln!("[DEBUG] Fire paused and reset");
-                        fire.pause_fire();
+                        fire.clear();
                     } else {
                         eprintln!("[DEBUG] Fire paused (frozen)");
                     }
                 }
 
                 // While paused, take screenshots periodically.
-                if do_screen_burn {
+                if do_screen_burn && let Some(capturer) = &capturer {
                     if !use_static_burn_image && last_screenshot_time.elapsed() >= Duration::from_millis(500) {
                         last_screenshot_time = Instant::now();
                         eprintln!("[DEBUG] Taking screenshot while paused");
                         // Spawn screenshots on background thread
                         let tx = screenshot_tx.clone();
+                        let capturer = capturer.clone();
                         rayon::spawn(move || {
-                            let results = take_screenshots_sync(&get_outputs_covered());
+                            // A capture that panicked mid-frame leaves nothing half-written worth giving up on
+                            let mut capturer = capturer.lock().unwrap_or_else(|e| e.into_inner());
+                            let results = take_screenshots_sync(capturer.as_mut(), &get_outputs_covered(), capture_size);
                             let _ = tx.send(results);
                         });
                     }
@@ -227,12 +478,53 @@ fn build_ui(app: &Application) {
                 if was_paused {
                     eprintln!("[DEBUG] Fire unpaused");
                     if restart_on_pause {
-                        fire.initialize_fire();
+                        fire.reset();
+                    }
+                }
+                if let Some(reactive) = reactive.as_mut() {
+                    reactive.apply(fire);
+                }
+                if let Some(rx) = &audio_rx {
+                    // Keep only the newest spectrum, but don't miss a beat from an older one
+                    let mut beat = false;
+                    while let Ok(frame) = rx.try_recv() {
+                        beat |= frame.beat;
+                        audio_frame = frame;
+                    }
+                    let row = bands_to_row(&audio_frame.bands, fire.width(), fire.max_heat());
+                    fire.set_source_heat(&row);
+                    if beat {
+                        let x = rand::random_range(0..fire.width());
+                        let y = fire.height() - 2;
+                        fire.spawn_burst(ParticleKind::Spark, x, y, beat_sparks);
                     }
                 }
+                if let Some(rx) = &compositor_events {
+                    let events = rx.try_iter().collect();
+                    window_sparks.update(&hypr_state.clients, events, fire);
+                }
+                if let Some(cursor_heat) = &cursor_heat {
+                    cursor_heat.apply(fire, &hypr_state.monitors);
+                }
+                if burn_window_borders {
+                    burn_window_edges(fire, &hypr_state.clients, scale);
+                }
                 fire.update(); // Update the fire state.
+                for text in &heat_texts {
+                    let heat = text.heat_value(fire.palette().len());
+                    fire.stamp_overlay(heat, &|mask, width, height| text.stamp(mask, width, height, 1));
+                }
+                if let Some(clock) = clock.as_ref().filter(|c| c.style == ClockStyle::Heat) {
+                    let heat = clock.heat_value(fire.palette().len());
+                    fire.stamp_overlay(heat, &|mask, width, height| clock.stamp(mask, width, height, 1));
+                }
                 if show_fps {
-                    draw_fps(&mut fire, current_fps);
+                    draw_fps(fire, current_fps);
+                }
+                if let Some(stats) = stats_overlay.as_mut() {
+                    stats.refresh();
+                    let max_heat = fire.max_heat();
+                    fire.stamp_overlay(max_heat, &|mask, width, height| stats.stamp(mask, width, height, 1));
                 }
             }
             
@@ -240,28 +532,24 @@ fn build_ui(app: &Application) {
                 if let Some((name, _)) = covered_outputs.iter().find(|(_, c)| !*c) {
                     if let Some(img) = last_screenshot.remove(name) {
                         last_screenshot.clear();
-                        let resized = img.resize_exact(
-                            fire.width as u32,
-                            fire.height as u32,
-                            image::imageops::FilterType::Triangle,
-                        );
-                        for y in 0..fire.height {
-                            for x in 0..fire.width {
-                                let px = resized.get_pixel(x as u32, y as u32);
-                                let r_diff = (px[0] as i32 - background_color[0] as i32).abs();
-                                let g_diff = (px[1] as i32 - background_color[1] as i32).abs();
-                                let b_diff = (px[2] as i32 - background_color[2] as i32).abs();
-                                let distance = (r_diff + g_diff + b_diff) as f32;
-                                let max_dist = 255.0 * 3.0;
-                                let idx = ((distance / max_dist) * (fire.palette.len() as f32 - 1.0)).round() as u8;
-                                let current_idx = y * fire.width + x;
-                                let fire_idx = &mut fire.pixel_buffer[current_idx];
-                                *fire_idx = (*fire_idx).max(idx);
+                        // Only burn where windows on this output went away; without geometry, burn the whole output
+                        let mut rects: Vec<CellRect> = vanished_clients
+                            .iter()
+                            .filter(|c| &c.monitor == name)
+                            .filter_map(|c| client_cells(c, scale, fire.width(), fire.height()))
+                            .collect();
+                        if rects.is_empty() {
+                            rects.push(CellRect { x: 0, y: 0, width: fire.width(), height: fire.height() });
+                        }
+                        for rect in rects {
+                            if !fire.show_screenshot(&img, rect) {
+                                burns.push(BurnIn::new(&img, rect, fire, background_color, &burn_style, fps));
                             }
                         }
-                        eprintln!("[DEBUG] Burn-in applied from screenshot");
+                        eprintln!("[DEBUG] Burn-in started from screenshot");
                     }
                 }
+                burns.retain_mut(|burn| burn.step(fire));
             }
             
             was_paused = paused;
@@ -269,10 +557,24 @@ fn build_ui(app: &Application) {
             {
                 // This block contains the rendering logic.
                 // It uses an immutable borrow of `fire` which is released at the end of the block.
-                let fire_palette = &fire.palette;
-                let fire_buffer = &fire.pixel_buffer;
-                let fire_width = fire.width;
-                let fire_height = fire.height;
+                let fire_palette = palette_fade.blend(fire.palette());
+                let fire_buffer = fire.heat();
+                let heat_fraction = if dither != Dither::None { fire.heat_fraction() } else { None };
+                let smoke_buffer = fire.smoke();
+                let image_layer = fire.image_layer();
+                let fire_width = fire.width();
+                let fire_height = fire.height();
+
+                let overlay_color = match &clock {
+                    Some(clock) if clock.style == ClockStyle::Solid => {
+                        overlay_mask.clear();
+                        overlay_mask.resize(fire_width * fire_height, 0);
+                        clock.stamp(&mut overlay_mask, fire_width, fire_height, 1);
+                        Some(clock.color)
+                    }
+                    _ => None,
+                };
+                let overlay_mask = &overlay_mask;
 
                 pixels
                 .par_chunks_mut(width * BYTES_PER_PIXEL)
@@ -282,7 +584,10 @@ fn build_ui(app: &Application) {
                     if fy < fire_height {
                         for fx in 0..fire_width {
                             let idx = fire_buffer[fy * fire_width + fx] as usize;
-                            let color = fire_palette[idx];
+                            let overlay = overlay_color.filter(|_| overlay_mask[fy * fire_width + fx] != 0);
+                            let smoke = smoke_buffer.map_or(0, |s| s[fy * fire_width + fx]);
+                            let image = image_layer.map_or([0; 4], |layer| layer[fy * fire_width + fx]);
+                            let color = overlay.unwrap_or_else(|| blend_image(blend_smoke(fire_palette[idx], smoke), image));
                             let start_wx = fx * scale;
                             let end_wx = ((fx + 1) * scale).min(width);
 
@@ -290,14 +595,25 @@ fn build_ui(app: &Application) {
                             let slice_end = end_wx * BYTES_PER_PIXEL;
 
                             if slice_end <= row.len() {
-                                if show_bg_image && idx == 0 {
+                                if show_bg_image && idx == 0 && smoke == 0 && image[3] == 0 && overlay.is_none() {
                                     if let Some(bg_bytes) = &bg_image_bytes {
                                         let bg_row_start = wy * width * BYTES_PER_PIXEL;
                                         let bg_slice = &bg_bytes[bg_row_start + slice_start .. bg_row_start + slice_end];
                                         row[slice_start..slice_end].copy_from_slice(bg_slice);
                                     }
                                 } else {
-                                    row[slice_start..slice_end].copy_from_slice(&color.repeat(end_wx - start_wx));
+                                    let fraction = heat_fraction.map_or(0, |f| f[fy * fire_width + fx]);
+                                    if fraction > 0 && overlay.is_none() {
+                                        // Dither between this entry and the next by how far the heat sits between them
+                                        let next = fire_palette[(idx + 1).min(fire_palette.len() - 1)];
+                                        let next = blend_image(blend_smoke(next, smoke), image);
+                                        for wx in start_wx..end_wx {
+                                            let pixel = if fraction > dither.threshold(wx, wy) { next } else { color };
+                                            row[wx * BYTES_PER_PIXEL..(wx + 1) * BYTES_PER_PIXEL].copy_from_slice(&pixel);
+                                        }
+                                    } else {
+                                        row[slice_start..slice_end].copy_from_slice(&color.repeat(end_wx - start_wx));
+                                    }
                                 }
                             }
                         }
@@ -324,67 +640,37 @@ fn build_ui(app: &Application) {
     });
 }
 
-fn take_screenshots_sync(covered_outputs: &Vec<(String, bool)>) -> Vec<(String, DynamicImage)> {
-    let mut results = Vec::new();
-    for (name, _covered) in covered_outputs {
-        eprintln!("[DEBUG] Taking screenshot for output: {}", name);
-        if let Ok(output) = std::process::Command::new("grim")
-            .args(["-o", name, "-"])
-            .output()
-        {
-            if output.status.success() {
-                if let Ok(i) = image::load_from_memory(&output.stdout) {
-                    results.push((name.clone(), i));
-                } else {
-                    eprintln!("[DEBUG] Failed to decode screenshot for {}", name);
-                }
-            } else {
-                eprintln!("[DEBUG] Screenshot command failed for {}", name);
-            }
-        }
-    }
-    results
-}
-
-fn draw_fps(fire: &mut DoomFire, fps: u32) {
+fn draw_fps(fire: &mut dyn Effect, fps: u32) {
     let fps_str = fps.to_string();
-    let char_width = 3;
-    let char_height = 5;
-    let spacing = 1;
     let padding_right = 5;
     let padding_top = 20;
 
-    let total_width = fps_str.len() * (char_width + spacing) - spacing;
+    let total_width = text_width(&fps_str, 1);
 
-    if fire.width < total_width + padding_right {
+    if fire.width() < total_width + padding_right {
         return;
     }
 
-    let start_x = fire.width - total_width - padding_right;
+    let start_x = fire.width() - total_width - padding_right;
     let start_y = padding_top;
-    let max_heat = if fire.palette.is_empty() {
+    let max_heat = if fire.palette().is_empty() {
         255
     } else {
-        (fire.palette.len() - 1) as u8
+        fire.max_heat()
     };
 
-    for (i, c) in fps_str.chars().enumerate() {
-        if let Some(digit) = c.to_digit(10) {
-            let digit = digit as usize;
-            let offset_x = start_x + i * (char_width + spacing);
-            for dy in 0..char_height {
-                for dx in 0..char_width {
-                    if FONT[digit][dy][dx] == 1 {
-                        let x = offset_x + dx;
-                        let y = start_y + dy;
-                        if y < fire.height && x < fire.width {
-                            fire.pixel_buffer[y * fire.width + x] = max_heat;
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let at = Placement { x: start_x, y: start_y, size: 1 };
+    fire.stamp_overlay(max_heat, &|mask, width, height| stamp_text(mask, width, height, &fps_str, at, 1));
+}
+
+/// Maps a client's monitor-local pixel rectangle onto the fire grid, clipped to it.
+fn client_cells(client: &ClientRect, scale: usize, width: usize, height: usize) -> Option<CellRect> {
+    let scale = scale.max(1) as i32;
+    let x0 = (client.x / scale).clamp(0, width as i32) as usize;
+    let y0 = (client.y / scale).clamp(0, height as i32) as usize;
+    let x1 = ((client.x + client.width) / scale).clamp(0, width as i32) as usize;
+    let y1 = ((client.y + client.height) / scale).clamp(0, height as i32) as usize;
+    (x1 > x0 && y1 > y0).then_some(CellRect { x: x0, y: y0, width: x1 - x0, height: y1 - y0 })
 }
 
 fn resolve_path(path: &str) -> PathBuf {
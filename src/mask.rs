@@ -0,0 +1,13 @@
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use std::path::Path;
+
+/// Loads a monochrome mask image and scales it to the fire grid.
+/// Bright, opaque pixels become solid cells; dark or transparent pixels stay open.
+pub fn load_mask(path: &Path, width: usize, height: usize) -> Result<Vec<bool>> {
+    let img = image::open(path).with_context(|| format!("Failed to load mask at {:?}", path))?;
+    let resized = img
+        .resize_exact(width as u32, height as u32, FilterType::Nearest)
+        .to_luma_alpha8();
+    Ok(resized.pixels().map(|p| p[0] >= 128 && p[1] >= 128).collect())
+}
@@ -0,0 +1,262 @@
+use crate::config::Config;
+use crate::effect::{Effect, Grid, Reactions};
+use crate::particle::ParticleKind;
+use crate::text::{stamp_text, Placement, CHAR_HEIGHT, CHAR_WIDTH};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+/// Size of one glyph slot in cells, including the gap to its neighbours.
+const SLOT_WIDTH: usize = CHAR_WIDTH + 1;
+const SLOT_HEIGHT: usize = CHAR_HEIGHT + 1;
+/// Chance per frame that a lit glyph flickers to a different character.
+const FLICKER_CHANCE: f64 = 0.02;
+
+/// The falling head of one column of glyphs.
+struct Drop {
+    head: f32,  // Glyph row the head is on
+    speed: f32, // Glyph rows per second
+    delay: f32, // Seconds until the column starts falling again
+}
+
+/// Matrix-style digital rain: columns of glyphs fall at varying speeds, leaving trails that fade
+/// down the palette. Burned-in screenshots dissolve and drip down the screen.
+pub struct MatrixRain {
+    grid: Grid,
+    columns: usize,
+    rows: usize,
+    glyphs: Vec<char>,     // Character shown in each slot
+    brightness: Vec<f32>,  // 0.0 - 1.0 per slot, 1.0 where a head just passed
+    drops: Vec<Drop>,
+    residue: Vec<u8>,      // Burned-in heat per cell that drips down and fades
+    speed: f32,
+    trail: f32,
+    density: f32,
+    intensity: f32,
+    frame_time: f32,
+    rng: ThreadRng,
+}
+
+impl MatrixRain {
+    pub fn new(config: &Config) -> Self {
+        let scale = config.scale.unwrap_or(1).max(1);
+        let width = config.screen_width.unwrap_or(1920) / scale;
+        let height = config.screen_height.unwrap_or(1080) / scale;
+        let matrix = config.matrix.as_ref();
+        let mut rain = Self {
+            grid: Grid::new(config),
+            columns: 0,
+            rows: 0,
+            glyphs: Vec::new(),
+            brightness: Vec::new(),
+            drops: Vec::new(),
+            residue: Vec::new(),
+            speed: matrix.and_then(|m| m.speed).unwrap_or(12.0).max(0.1),
+            trail: matrix.and_then(|m| m.trail).unwrap_or(12.0).max(1.0),
+            density: matrix.and_then(|m| m.density).unwrap_or(0.7).clamp(0.05, 1.0),
+            intensity: 1.0,
+            frame_time: 1.0 / config.fps.unwrap_or(10).max(1) as f32,
+            rng: rand::rng(),
+        };
+        rain.resize(width, height);
+        rain
+    }
+
+    fn random_glyph(&mut self) -> char {
+        self.rng.random_range('!'..='~')
+    }
+
+    /// Sends a column back to the top after a random pause; sparser rain means longer pauses.
+    fn respawn(&mut self, column: usize, max_delay: f32) {
+        let speed = self.speed * self.rng.random_range(0.5..1.5);
+        let delay = self.rng.random_range(0.0..=max_delay);
+        self.drops[column] = Drop { head: 0.0, speed, delay };
+    }
+
+    /// Longest pause between drops in one column, in seconds.
+    fn max_delay(&self) -> f32 {
+        let fall_time = (self.rows as f32 + self.trail) / self.speed;
+        fall_time * (1.0 / self.density - 1.0) * 2.0
+    }
+
+    /// Starts the column under cell `x` falling from glyph row `row` right away, if it's idle.
+    fn start_drop(&mut self, x: usize, row: usize) {
+        let column = (x / SLOT_WIDTH).min(self.columns.saturating_sub(1));
+        if let Some(drop) = self.drops.get_mut(column)
+            && drop.delay > 0.0
+        {
+            drop.delay = 0.0;
+            drop.head = row as f32;
+        }
+    }
+
+    /// Lets burned-in heat drip down a cell at a time and fade.
+    fn dissolve_residue(&mut self) {
+        let heat_scale = self.grid.palette.heat_scale();
+        for y in (0..self.grid.height).rev() {
+            for x in 0..self.grid.width {
+                let idx = y * self.grid.width + x;
+                let value = self.residue[idx];
+                if value == 0 {
+                    continue;
+                }
+                let faded = value.saturating_sub((self.rng.random_range(0..=2) as f32 * heat_scale).round() as u8);
+                self.residue[idx] = 0;
+                if y + 1 < self.grid.height && self.rng.random_bool(0.5) {
+                    let below = idx + self.grid.width;
+                    self.residue[below] = self.residue[below].max(faded);
+                } else {
+                    self.residue[idx] = faded;
+                }
+            }
+        }
+    }
+}
+
+impl Effect for MatrixRain {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        let dt = self.frame_time * self.intensity;
+        // A glyph fades out over the time it takes a head to fall `trail` rows
+        let fade = dt * self.speed / self.trail;
+        self.brightness.iter_mut().for_each(|b| *b = (*b - fade).max(0.0));
+
+        let max_delay = self.max_delay();
+        for column in 0..self.columns {
+            let drop = &mut self.drops[column];
+            if drop.delay > 0.0 {
+                drop.delay -= dt;
+                continue;
+            }
+            let from = drop.head as usize;
+            drop.head += drop.speed * dt;
+            let to = (drop.head as usize).min(self.rows);
+            let done = drop.head >= self.rows as f32 + self.trail;
+            // Light every slot the head entered this frame, so fast drops don't leave gaps
+            for row in from..to {
+                let slot = row * self.columns + column;
+                self.brightness[slot] = 1.0;
+                self.glyphs[slot] = self.random_glyph();
+            }
+            if done {
+                self.respawn(column, max_delay);
+            }
+        }
+
+        for slot in 0..self.glyphs.len() {
+            if self.brightness[slot] > 0.0 && self.rng.random_bool(FLICKER_CHANCE) {
+                self.glyphs[slot] = self.random_glyph();
+            }
+        }
+
+        self.dissolve_residue();
+
+        let max_heat = self.max_heat() as f32;
+        self.grid.heat.copy_from_slice(&self.residue);
+        let mut buf = [0u8; 4];
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let slot = row * self.columns + column;
+                let value = (self.brightness[slot] * max_heat).round() as u8;
+                if value == 0 {
+                    continue;
+                }
+                let glyph = self.glyphs[slot].encode_utf8(&mut buf);
+                let at = Placement { x: column * SLOT_WIDTH, y: row * SLOT_HEIGHT, size: 1 };
+                stamp_text(&mut self.grid.heat, self.grid.width, self.grid.height, glyph, at, value);
+            }
+        }
+        self.grid.blank_solid();
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+        // Stagger the first drops so the screen fills up gradually
+        let fall_time = self.rows as f32 / self.speed;
+        let max_delay = self.max_delay();
+        for column in 0..self.columns {
+            let stagger = self.rng.random_range(0.0..=fall_time);
+            self.respawn(column, max_delay);
+            self.drops[column].delay += stagger;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+        self.brightness.iter_mut().for_each(|b| *b = 0.0);
+        self.residue.iter_mut().for_each(|x| *x = 0);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.grid.resize(width, height);
+        self.columns = width / SLOT_WIDTH;
+        self.rows = height / SLOT_HEIGHT;
+        self.residue = vec![0; width * height];
+        self.glyphs = vec![' '; self.columns * self.rows];
+        self.brightness = vec![0.0; self.columns * self.rows];
+        self.drops = (0..self.columns).map(|_| Drop { head: 0.0, speed: self.speed, delay: 0.0 }).collect();
+        self.reset();
+    }
+
+    /// Burned-in heat goes to the residue layer, which dissolves into the rain.
+    fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+        if x >= self.grid.width || y >= self.grid.height {
+            return;
+        }
+        let idx = y * self.grid.width + x;
+        if !self.grid.solid[idx] {
+            self.residue[idx] = self.residue[idx].max(heat);
+        }
+    }
+
+    /// Lights up (positive `amount`) or dims (negative `amount`) the glyphs around (`x`, `y`).
+    fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let cx = (column * SLOT_WIDTH + CHAR_WIDTH / 2) as f32 - x as f32;
+                let cy = (row * SLOT_HEIGHT + CHAR_HEIGHT / 2) as f32 - y as f32;
+                let distance = (cx * cx + cy * cy).sqrt();
+                if distance > radius as f32 {
+                    continue;
+                }
+                let falloff = 1.0 - distance / (radius as f32 + 1.0);
+                let slot = row * self.columns + column;
+                if amount >= 0.0 {
+                    if self.brightness[slot] == 0.0 {
+                        self.glyphs[slot] = self.random_glyph();
+                    }
+                    self.brightness[slot] = self.brightness[slot].max(amount * falloff);
+                } else {
+                    self.brightness[slot] *= 1.0 + amount * falloff;
+                }
+            }
+        }
+    }
+}
+
+impl Reactions for MatrixRain {
+    /// Hot spots in the source row start idle columns above them falling.
+    fn set_source_heat(&mut self, heat: &[u8]) {
+        let threshold = self.max_heat() / 2;
+        for (x, &h) in heat.iter().enumerate().step_by(SLOT_WIDTH) {
+            if h > threshold {
+                self.start_drop(x, 0);
+            }
+        }
+    }
+
+    /// Starts a drop from the glyph under (`x`, `y`) instead of throwing particles.
+    fn spawn_burst(&mut self, _kind: ParticleKind, x: usize, y: usize, _count: usize) {
+        self.start_drop(x, y / SLOT_HEIGHT);
+    }
+
+    fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity.max(0.0) as f32;
+    }
+}
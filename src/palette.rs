@@ -0,0 +1,159 @@
+use crate::config::Config;
+use crate::fire_types::{generate_palette, FireType, PALETTE_LEN};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// Ordered dithering thresholds, 0 - 63.
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// An effect's palette and the fire type it was generated from, or an imported palette that replaces it.
+/// Switching fire type keeps the current length, so heat already on screen stays within the palette.
+pub struct Palette {
+    pub fire_type: FireType,
+    pub background: Option<[u8; 3]>,
+    pub colours: Vec<[u8; 3]>,
+    custom: Option<Vec<[u8; 3]>>,
+}
+
+impl Palette {
+    /// Generates `fire_type`'s palette, stretched to the configured `palette_size`.
+    pub fn new(fire_type: FireType, config: &Config) -> Self {
+        let len = config.palette_size.unwrap_or(PALETTE_LEN).clamp(2, 256);
+        Self {
+            fire_type,
+            background: config.background,
+            colours: resample(&generate_palette(fire_type, config.background, 0.0), len),
+            custom: None,
+        }
+    }
+
+    /// Palette steps per step of the built-in palettes, for scaling decay rates so effects look the same at any length.
+    pub fn heat_scale(&self) -> f32 {
+        self.colours.len().saturating_sub(1) as f32 / (PALETTE_LEN - 1) as f32
+    }
+
+    /// Switches to a built-in fire type, dropping any imported palette.
+    pub fn set_fire_type(&mut self, fire_type: FireType) {
+        self.fire_type = fire_type;
+        self.custom = None;
+        self.regenerate(self.background, 0.0);
+    }
+
+    /// Replaces the fire type's colours with an imported palette.
+    pub fn set_custom(&mut self, colours: Vec<[u8; 3]>) {
+        self.custom = Some(colours);
+        self.regenerate(self.background, 0.0);
+    }
+
+    /// Rebuilds the colours at the given animation phase (only Aurora uses it).
+    pub fn regenerate(&mut self, background: Option<[u8; 3]>, phase: f32) {
+        let colours = match &self.custom {
+            Some(custom) => custom.clone(),
+            None => generate_palette(self.fire_type, background, phase),
+        };
+        self.colours = resample(&colours, self.colours.len());
+    }
+}
+
+/// Stretches or squeezes a palette to `len` entries, interpolating between neighbouring colours.
+pub fn resample(palette: &[[u8; 3]], len: usize) -> Vec<[u8; 3]> {
+    if palette.len() == len || palette.is_empty() {
+        return palette.to_vec();
+    }
+    let last = (palette.len() - 1) as f32;
+    (0..len)
+        .map(|i| {
+            let pos = if len > 1 { i as f32 * last / (len - 1) as f32 } else { 0.0 };
+            let (lo, t) = (pos.floor() as usize, pos.fract());
+            let hi = (lo + 1).min(palette.len() - 1);
+            lerp_colour(palette[lo], palette[hi], t)
+        })
+        .collect()
+}
+
+fn lerp_colour(a: [u8; 3], b: [u8; 3], t: f32) -> [u8; 3] {
+    [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * t).round() as u8)
+}
+
+/// How heat that falls between two palette entries is drawn, from the `dither` option.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Dither {
+    /// Rounds down to the nearest entry.
+    #[default]
+    None,
+    /// An 8x8 Bayer matrix: a fine, regular crosshatch.
+    Ordered,
+    /// Interleaved gradient noise, which scatters like blue noise without needing a texture.
+    BlueNoise,
+}
+
+impl Dither {
+    /// The threshold for screen pixel (`x`, `y`): heat whose fraction of a step is above it is drawn with the next entry.
+    pub fn threshold(self, x: usize, y: usize) -> u8 {
+        match self {
+            Dither::None => u8::MAX,
+            Dither::Ordered => BAYER_8X8[y % 8][x % 8] * 4 + 2,
+            Dither::BlueNoise => {
+                let noise = (52.982_918 * (0.067_110_56 * x as f32 + 0.005_837_15 * y as f32).fract()).fract();
+                (noise * 255.0) as u8
+            }
+        }
+    }
+}
+
+/// Cross-fades from whatever palette was on screen to a new one over a fixed duration. The target
+/// is passed in every frame, so palettes that animate themselves keep animating during the fade.
+pub struct PaletteFade {
+    duration: Duration,
+    started: Option<Instant>,
+    from: Vec<[u8; 3]>,
+    shown: Vec<[u8; 3]>,
+}
+
+impl PaletteFade {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            started: None,
+            from: Vec::new(),
+            shown: Vec::new(),
+        }
+    }
+
+    /// Starts fading out the palette drawn last frame.
+    pub fn start(&mut self) {
+        self.from = self.shown.clone();
+        self.started = Some(Instant::now());
+    }
+
+    /// Returns the palette to draw this frame: `target`, blended from the old palette while a fade runs.
+    pub fn blend(&mut self, target: &[[u8; 3]]) -> &[[u8; 3]] {
+        let progress = match self.started {
+            Some(started) if !self.duration.is_zero() && !self.from.is_empty() => {
+                started.elapsed().as_secs_f32() / self.duration.as_secs_f32()
+            }
+            _ => 1.0,
+        };
+        if progress >= 1.0 {
+            self.started = None;
+            self.shown.clear();
+            self.shown.extend_from_slice(target);
+        } else {
+            // Smoothstep so the fade eases in and out
+            let t = progress * progress * (3.0 - 2.0 * progress);
+            let from = resample(&self.from, target.len());
+            self.shown = from.iter().zip(target).map(|(&a, &b)| lerp_colour(a, b, t)).collect();
+        }
+        &self.shown
+    }
+}
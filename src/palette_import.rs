@@ -0,0 +1,310 @@
+use crate::palette::resample;
+use anyhow::{bail, Context, Result};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the palette file is checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+/// Colours kept when extracting a palette from an image, and the share of pixels a colour needs to count.
+const DOMINANT_COLOURS: usize = 8;
+const MIN_COLOUR_SHARE: f32 = 0.02;
+
+/// A palette imported from a file, re-imported whenever the file changes (e.g. after running `wal`).
+pub struct PaletteFile {
+    path: PathBuf,
+    background: Option<[u8; 3]>,
+    len: usize,
+    modified: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl PaletteFile {
+    pub fn new(path: PathBuf, background: Option<[u8; 3]>, len: usize) -> Self {
+        Self {
+            path,
+            background,
+            len,
+            modified: None,
+            next_check: Instant::now() + WATCH_INTERVAL,
+        }
+    }
+
+    pub fn load(&mut self) -> Result<Vec<[u8; 3]>> {
+        self.modified = modified_time(&self.path);
+        import_palette(&self.path, self.background, self.len)
+    }
+
+    /// Called every frame; returns the re-imported palette when the file has changed since the last load.
+    pub fn poll(&mut self) -> Option<Vec<[u8; 3]>> {
+        if Instant::now() < self.next_check {
+            return None;
+        }
+        self.next_check = Instant::now() + WATCH_INTERVAL;
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        eprintln!("[DEBUG] Palette file {:?} changed, re-importing", self.path);
+        self.load().map_err(|e| eprintln!("{:#}", e)).ok()
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Imports a palette from a GIMP `.gpl` palette, a GIMP `.ggr` gradient, a pywal `colors.json` or an image.
+/// Like `generate_palette`, the result has `len` entries running from the background colour (or black)
+/// up to the hottest colour.
+pub fn import_palette(path: &Path, background: Option<[u8; 3]>, len: usize) -> Result<Vec<[u8; 3]>> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    let colours = match extension.as_str() {
+        "gpl" => by_luminance(read_gpl(&read_text(path)?)?),
+        "ggr" => read_ggr(&read_text(path)?, len - 1)?,
+        "json" => by_luminance(read_pywal(&read_text(path)?)?),
+        _ => {
+            let img = image::open(path).with_context(|| format!("Failed to load palette image at {:?}", path))?;
+            by_luminance(dominant_colours(&img))
+        }
+    };
+    if colours.is_empty() {
+        bail!("No colours found in palette {:?}", path);
+    }
+    let mut palette = vec![background.unwrap_or([0, 0, 0])];
+    palette.extend(resample(&colours, len - 1));
+    Ok(palette)
+}
+
+fn read_text(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read palette at {:?}", path))
+}
+
+/// Swatches without repeats, sorted from darkest to brightest so they map onto heat like a fire palette.
+fn by_luminance(mut colours: Vec<[u8; 3]>) -> Vec<[u8; 3]> {
+    // Dedup first: after sorting, repeats can be split up by other colours of the same luminance
+    let mut seen = HashSet::new();
+    colours.retain(|c| seen.insert(*c));
+    colours.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+    colours
+}
+
+fn luminance(c: [u8; 3]) -> f32 {
+    0.2126 * c[0] as f32 + 0.7152 * c[1] as f32 + 0.0722 * c[2] as f32
+}
+
+/// Reads the `R G B name` lines of a GIMP palette.
+fn read_gpl(text: &str) -> Result<Vec<[u8; 3]>> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        bail!("Not a GIMP palette");
+    }
+    let mut colours = Vec::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+        let channels: Vec<u8> = line.split_whitespace().take(3).filter_map(|v| v.parse().ok()).collect();
+        match channels[..] {
+            [r, g, b] => colours.push([r, g, b]),
+            _ => bail!("Bad colour line '{}' in GIMP palette", line),
+        }
+    }
+    Ok(colours)
+}
+
+/// One segment of a GIMP gradient.
+struct Segment {
+    left: f32,
+    middle: f32,
+    right: f32,
+    from: [f32; 3],
+    to: [f32; 3],
+    blend: u8, // 0 linear, 1 curved, 2 sine, 3 sphere increasing, 4 sphere decreasing
+}
+
+impl Segment {
+    fn colour_at(&self, x: f32) -> [f32; 3] {
+        let width = (self.right - self.left).max(f32::EPSILON);
+        let pos = ((x - self.left) / width).clamp(0.0, 1.0);
+        let mid = ((self.middle - self.left) / width).clamp(0.001, 0.999);
+        let factor = match self.blend {
+            1 => pos.powf(0.5f32.ln() / mid.ln()),
+            _ if pos <= mid => 0.5 * pos / mid,
+            _ => 0.5 + 0.5 * (pos - mid) / (1.0 - mid),
+        };
+        let factor = match self.blend {
+            2 => ((-PI / 2.0 + PI * factor).sin() + 1.0) / 2.0,
+            3 => (1.0 - (factor - 1.0).powi(2)).sqrt(),
+            4 => 1.0 - (1.0 - factor * factor).sqrt(),
+            _ => factor,
+        };
+        [0, 1, 2].map(|c| self.from[c] + (self.to[c] - self.from[c]) * factor)
+    }
+}
+
+/// Samples a GIMP gradient at `samples` evenly spaced points. HSV segments are blended in RGB.
+fn read_ggr(text: &str, samples: usize) -> Result<Vec<[u8; 3]>> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    if lines.next() != Some("GIMP Gradient") {
+        bail!("Not a GIMP gradient");
+    }
+    let mut lines = lines.skip_while(|l| l.starts_with("Name:"));
+    let count: usize = lines.next().and_then(|l| l.parse().ok()).context("Missing segment count in GIMP gradient")?;
+    let segments = lines
+        .take(count)
+        .map(|line| {
+            let v: Vec<f32> = line.split_whitespace().map(|v| v.parse()).collect::<Result<_, _>>()
+                .with_context(|| format!("Bad segment '{}' in GIMP gradient", line))?;
+            if v.len() < 13 {
+                bail!("Bad segment '{}' in GIMP gradient", line);
+            }
+            Ok(Segment {
+                left: v[0],
+                middle: v[1],
+                right: v[2],
+                from: [v[3], v[4], v[5]],
+                to: [v[7], v[8], v[9]],
+                blend: v[11] as u8,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if segments.is_empty() {
+        bail!("GIMP gradient has no segments");
+    }
+    Ok((0..samples)
+        .map(|i| {
+            let x = if samples > 1 { i as f32 / (samples - 1) as f32 } else { 0.0 };
+            let segment = segments.iter().find(|s| x <= s.right).unwrap_or(&segments[segments.len() - 1]);
+            segment.colour_at(x).map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8)
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct PywalColours {
+    colors: HashMap<String, String>,
+}
+
+/// Reads `color0` to `color15` from pywal's `colors.json`.
+fn read_pywal(text: &str) -> Result<Vec<[u8; 3]>> {
+    let wal: PywalColours = serde_json::from_str(text).context("Not a pywal colors.json")?;
+    (0..16)
+        .filter_map(|i| wal.colors.get(&format!("color{}", i)))
+        .map(|hex| parse_hex(hex))
+        .collect()
+}
+
+fn parse_hex(hex: &str) -> Result<[u8; 3]> {
+    let digits = hex.trim().trim_start_matches('#');
+    let value = u32::from_str_radix(digits, 16).ok().filter(|_| digits.len() == 6);
+    let value = value.with_context(|| format!("Bad colour '{}'", hex))?;
+    Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+/// The most common colours in an image, found by bucketing a thumbnail's pixels by their top bits.
+fn dominant_colours(img: &DynamicImage) -> Vec<[u8; 3]> {
+    // Nearest keeps the image's own colours rather than blends along edges
+    let thumbnail = img.resize(64, 64, FilterType::Nearest).to_rgb8();
+    let min_count = (thumbnail.len() / 3) as f32 * MIN_COLOUR_SHARE;
+    // Per bucket: pixel count and channel sums, for the bucket's average colour
+    let mut buckets: HashMap<[u8; 3], (u32, [u32; 3])> = HashMap::new();
+    for p in thumbnail.pixels() {
+        let entry = buckets.entry([p[0] >> 5, p[1] >> 5, p[2] >> 5]).or_default();
+        entry.0 += 1;
+        for c in 0..3 {
+            entry.1[c] += p[c] as u32;
+        }
+    }
+    let mut buckets: Vec<(u32, [u32; 3])> = buckets.into_values().collect();
+    buckets.sort_by_key(|b| std::cmp::Reverse(b.0));
+    // A noisy photo may have no colour over the share, so the most common one always counts
+    let min_count = buckets.first().map_or(0.0, |b| min_count.min(b.0 as f32));
+    buckets
+        .iter()
+        .take(DOMINANT_COLOURS)
+        .filter(|(count, _)| *count as f32 >= min_count)
+        .map(|(count, sum)| sum.map(|s| (s / count) as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_gimp_palette() {
+        let text = "GIMP Palette\nName: Embers\nColumns: 3\n# comment\n255 128   0 Orange\n  0   0   0 Black\n255 255 255\n";
+        assert_eq!(read_gpl(text).unwrap(), vec![[255, 128, 0], [0, 0, 0], [255, 255, 255]]);
+        assert!(read_gpl("Not a palette\n1 2 3\n").is_err());
+        assert!(read_gpl("GIMP Palette\n12 34\n").is_err());
+    }
+
+    #[test]
+    fn reads_gimp_gradient() {
+        // Linear black to red, then curved red to white with its midpoint pulled left
+        let text = "GIMP Gradient\nName: Heat\n2\n\
+            0.0 0.25 0.5 0 0 0 1 1 0 0 1 0 0\n\
+            0.5 0.6 1.0 1 0 0 1 1 1 1 1 1 0\n";
+        let colours = read_ggr(text, 5).unwrap();
+        assert_eq!(colours.len(), 5);
+        assert_eq!(colours[0], [0, 0, 0]);
+        assert_eq!(colours[1], [128, 0, 0]);
+        assert_eq!(colours[2], [255, 0, 0]);
+        assert_eq!(colours[4], [255, 255, 255]);
+        // The curved blend passes the midpoint early, so three quarters along is already past half white
+        assert!(colours[3][1] > 128 && colours[3][1] == colours[3][2]);
+        assert!(read_ggr("GIMP Gradient\n1\n0 0.5 1 0 0 0\n", 3).is_err());
+        assert!(read_ggr("GIMP Gradient\n0\n", 3).is_err());
+    }
+
+    #[test]
+    fn segment_blends_reach_half_at_their_middle() {
+        for blend in [0, 1] {
+            let segment = Segment { left: 0.2, middle: 0.3, right: 1.0, from: [0.0; 3], to: [1.0, 0.5, 0.0], blend };
+            let mid = segment.colour_at(0.3);
+            assert!((mid[0] - 0.5).abs() < 1e-4 && (mid[1] - 0.25).abs() < 1e-4, "blend {} gave {:?}", blend, mid);
+            assert_eq!(segment.colour_at(0.0), [0.0; 3]);
+            assert_eq!(segment.colour_at(1.0), [1.0, 0.5, 0.0]);
+        }
+        // Curved is one smooth power curve, not two straight halves meeting at the middle
+        let linear = Segment { left: 0.0, middle: 0.2, right: 1.0, from: [0.0; 3], to: [1.0; 3], blend: 0 };
+        let curved = Segment { blend: 1, ..linear };
+        assert!(curved.colour_at(0.4)[0] > linear.colour_at(0.4)[0]);
+    }
+
+    #[test]
+    fn reads_pywal_colours_in_order() {
+        let text = r##"{"wallpaper": "/tmp/a.png", "special": {"background": "#000000"},
+            "colors": {"color1": "#ff0000", "color0": "#101010", "color2": "#00FF00"}}"##;
+        assert_eq!(read_pywal(text).unwrap(), vec![[16, 16, 16], [255, 0, 0], [0, 255, 0]]);
+        assert!(read_pywal(r#"{"colors": {"color0": "red"}}"#).is_err());
+        assert!(read_pywal("[]").is_err());
+    }
+
+    #[test]
+    fn parses_hex_colours() {
+        assert_eq!(parse_hex("#1a2B3c").unwrap(), [0x1a, 0x2b, 0x3c]);
+        assert_eq!(parse_hex(" ffffff ").unwrap(), [255, 255, 255]);
+        for bad in ["#fff", "#1234567", "#gggggg", ""] {
+            assert!(parse_hex(bad).is_err(), "{:?} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn by_luminance_drops_repeats_of_equal_luminance() {
+        // Red and a grey of the same luminance alternate, so sorting alone leaves the repeats apart
+        let red = [255, 0, 0];
+        let grey = [54, 54, 54];
+        assert_eq!(luminance(red).round(), luminance(grey).round());
+        let sorted = by_luminance(vec![[255, 255, 255], red, grey, red, [0, 0, 0], grey]);
+        assert_eq!(sorted.len(), 4);
+        assert_eq!(sorted[0], [0, 0, 0]);
+        assert_eq!(sorted[3], [255, 255, 255]);
+    }
+}
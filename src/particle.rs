@@ -1,109 +1,368 @@
+use crate::config::ParticlesConfig;
 use crate::fire_types::FireType;
 use rand::prelude::*;
 
+const WIND_COUPLING: f32 = 0.1; // How quickly horizontal velocity follows the wind
+const TURBULENCE: f32 = 0.3; // Random horizontal jitter per frame
+const SPARK_TRAIL: usize = 3; // Cells drawn behind a spark, fading towards the tail
+
+/// What a particle looks like and how it moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ParticleKind {
+    /// Glowing heat that drifts up slowly and fades over a long life.
+    Ember,
+    /// Fast, bright and short-lived, drawn with a short trail.
+    Spark,
+    /// Grey haze blended over the fire rather than burning in it.
+    Smoke,
+    /// Grey flecks that fall down through the flames.
+    Ash,
+}
+
+impl ParticleKind {
+    /// Upward acceleration in cells per frame squared; negative values fall.
+    fn buoyancy(self) -> f32 {
+        match self {
+            ParticleKind::Ember => 0.04,
+            ParticleKind::Spark => 0.08,
+            ParticleKind::Smoke => 0.02,
+            ParticleKind::Ash => -0.02,
+        }
+    }
+
+    /// Fraction of velocity lost each frame.
+    fn drag(self) -> f32 {
+        match self {
+            ParticleKind::Ember => 0.05,
+            ParticleKind::Spark => 0.02,
+            ParticleKind::Smoke => 0.1,
+            ParticleKind::Ash => 0.1,
+        }
+    }
+
+    /// Multiplies the launch velocity.
+    fn speed(self) -> f32 {
+        match self {
+            ParticleKind::Ember => 0.6,
+            ParticleKind::Spark => 2.5,
+            ParticleKind::Smoke => 0.4,
+            ParticleKind::Ash => 0.2,
+        }
+    }
+}
+
+/// A particle with sub-cell position and velocity. Positions are in fire-grid cells, `y` grows downwards.
 #[derive(Clone, Copy)]
 pub struct Particle {
-    pub x: usize,
-    pub y: usize,
+    pub kind: ParticleKind,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
     pub life: u8,
+    pub max_life: u8,
     pub color_idx: u8,
 }
 
-/// Spawns a new particle with fire-type-specific logic, if the random chance is met.
+impl Particle {
+    /// Creates a particle at a cell with a slightly randomised velocity for its kind.
+    fn launch(rng: &mut ThreadRng, kind: ParticleKind, x: usize, y: usize, life: u8, color_idx: u8) -> Self {
+        let speed = kind.speed();
+        let rise = rng.random_range(0.7..=1.3) * speed;
+        Self {
+            kind,
+            x: x as f32 + 0.5,
+            y: y as f32 + 0.5,
+            vx: rng.random_range(-0.5..=0.5) * speed,
+            vy: if kind == ParticleKind::Ash { rise * 0.5 } else { -rise },
+            life,
+            max_life: life.max(1),
+            color_idx,
+        }
+    }
+}
+
+/// Heat colours as (weight, low, high) fractions of the palette.
+type ColorRanges = &'static [(f64, f32, f32)];
+
+/// Per-fire-type particle settings.
+pub struct ParticleProfile {
+    /// Embers, sparks, smoke puffs and ash flecks spawned per 1000 columns per second.
+    pub ember: f64,
+    pub spark: f64,
+    pub smoke: f64,
+    pub ash: f64,
+    /// Live particles beyond this stop new ones spawning.
+    pub max_count: usize,
+    /// One range is picked by weight for each new particle.
+    pub colors: ColorRanges,
+    /// Overrides `colors` with an inclusive range of palette indices.
+    pub color_range: Option<(u8, u8)>,
+    /// Ember lifetime range in frames. Sparks live half as long, smoke and ash four times as long.
+    pub life: (u8, u8),
+}
+
+impl ParticleProfile {
+    /// Builds the fire type's defaults, then applies any overrides from the `[particles]` table.
+    pub fn new(fire_type: FireType, config: Option<&ParticlesConfig>) -> Self {
+        let mut profile = Self::for_fire_type(fire_type);
+        let Some(config) = config else {
+            return profile;
+        };
+        profile.ember = config.ember_rate.unwrap_or(profile.ember);
+        profile.spark = config.spark_rate.unwrap_or(profile.spark);
+        profile.smoke = config.smoke_rate.unwrap_or(profile.smoke);
+        profile.ash = config.ash_rate.unwrap_or(profile.ash);
+        // The switch wins over any rates set alongside it
+        if !config.enabled.unwrap_or(true) {
+            profile.ember = 0.0;
+            profile.spark = 0.0;
+            profile.smoke = 0.0;
+            profile.ash = 0.0;
+        }
+        profile.max_count = config.max_count.unwrap_or(profile.max_count);
+        if let Some([lo, hi]) = config.life {
+            let lo = lo.clamp(2, 250);
+            profile.life = (lo, hi.max(lo + 2));
+        }
+        profile.color_range = config.color_range.map(|[lo, hi]| (lo.min(hi), lo.max(hi)));
+        profile
+    }
+
+    /// Every type spawns embers at the original density, one roughly every seven frames on a 480-column grid
+    /// at 23 FPS. `Original` stops there; themed types add their own sparks, smoke or ash on top.
+    pub fn for_fire_type(fire_type: FireType) -> Self {
+        let (colors, life): (ColorRanges, (u8, u8)) = match fire_type {
+            FireType::Original | FireType::WhiteHot | FireType::White => (&[(1.0, 1.0, 1.0)], (10, 30)),
+            FireType::Blue | FireType::Ice => (&[(1.0, 0.7, 0.7)], (14, 32)),
+            FireType::Green | FireType::Toxic => (&[(1.0, 0.6, 0.6)], (12, 28)),
+            FireType::Purple => (&[(1.0, 0.8, 0.8)], (12, 28)),
+            FireType::Rainbow | FireType::Candy => (&[(1.0, 0.5, 1.0)], (10, 30)),
+            FireType::FireAndIce | FireType::Cyberpunk => (&[(0.5, 0.85, 0.85), (0.5, 0.15, 0.15)], (10, 28)),
+            FireType::ChemicalFire => (&[(1.0, 0.7, 0.7)], (10, 30)),
+            FireType::Aurora => (&[(1.0, 0.25, 0.75)], (16, 36)),
+            FireType::Plasma => (&[(0.3, 1.0, 1.0), (0.7, 0.33, 1.0)], (10, 28)),
+            FireType::Void => (&[(0.8, 0.0, 0.0), (0.2, 0.4, 0.4)], (18, 36)),
+        };
+        // (spark, smoke, ash) rates
+        let (spark, smoke, ash) = match fire_type {
+            FireType::Original => (0.0, 0.0, 0.0),
+            FireType::White | FireType::WhiteHot => (2.4, 0.0, 0.0),
+            FireType::ChemicalFire => (2.4, 2.4, 0.0),
+            FireType::Cyberpunk | FireType::Plasma => (3.6, 0.0, 0.0),
+            FireType::FireAndIce => (1.2, 0.0, 0.0),
+            FireType::Toxic => (0.0, 3.6, 0.0),
+            FireType::Ice => (0.0, 0.0, 1.2),
+            FireType::Void => (0.0, 0.0, 2.4),
+            FireType::Blue
+            | FireType::Green
+            | FireType::Purple
+            | FireType::Rainbow
+            | FireType::Candy
+            | FireType::Aurora => (0.0, 0.0, 0.0),
+        };
+        Self {
+            ember: 7.2,
+            spark,
+            smoke,
+            ash,
+            max_count: 2000,
+            colors,
+            color_range: None,
+            life,
+        }
+    }
+
+    fn pick_color(&self, rng: &mut ThreadRng, palette_len: usize) -> u8 {
+        let max_idx = (palette_len - 1).min(u8::MAX as usize) as u8;
+        if let Some((lo, hi)) = self.color_range {
+            return rng.random_range(lo.min(max_idx)..=hi.min(max_idx));
+        }
+        let (_, lo, hi) = self
+            .colors
+            .choose_weighted(rng, |c| c.0)
+            .copied()
+            .unwrap_or((1.0, 1.0, 1.0));
+        let frac = if hi > lo { rng.random_range(lo..=hi) } else { lo };
+        ((palette_len as f32 * frac) as usize).min(palette_len - 1) as u8
+    }
+}
+
+/// Spawns this frame's share of each particle kind. Rates are per 1000 columns per second,
+/// so wider grids get proportionally more particles and the frame rate doesn't change the density.
 pub fn maybe_spawn_particle(
     particles: &mut Vec<Particle>,
-    fire_type: FireType,
+    profile: &ParticleProfile,
     palette_len: usize,
+    solid: &[bool],
     width: usize,
     height: usize,
+    frame_time: f64,
 ) {
     let mut rng = ThreadRng::default();
-    if rng.random_bool(0.15) {
-        let px = rng.random_range(0..width);
-        let (color_idx, life) = match fire_type {
-            FireType::Original | FireType::WhiteHot => {
-                ((palette_len - 1) as u8, rng.random_range(10..30))
-            }
-            FireType::White => ((palette_len - 1) as u8, rng.random_range(10..30)),
-            FireType::Blue | FireType::Ice => {
-                ((palette_len as f32 * 0.7) as u8, rng.random_range(14..32))
+    let kinds = [
+        (ParticleKind::Ember, profile.ember),
+        (ParticleKind::Spark, profile.spark),
+        (ParticleKind::Smoke, profile.smoke),
+        (ParticleKind::Ash, profile.ash),
+    ];
+    for (kind, rate) in kinds {
+        // Spawn the whole part of the expected count, and one more with the fractional part as chance
+        let expected = (rate * width as f64 / 1000.0 * frame_time).max(0.0);
+        let count = expected as usize + rng.random_bool(expected.fract()) as usize;
+        for _ in 0..count {
+            if particles.len() >= profile.max_count {
+                return;
             }
-            FireType::Green | FireType::Toxic => {
-                ((palette_len as f32 * 0.6) as u8, rng.random_range(12..28))
-            }
-            FireType::Purple => ((palette_len as f32 * 0.8) as u8, rng.random_range(12..28)),
-            FireType::Rainbow | FireType::Candy => (
-                rng.random_range((palette_len / 2)..palette_len) as u8,
-                rng.random_range(10..30),
-            ),
-            FireType::FireAndIce => {
-                if rng.random_bool(0.5) {
-                    ((palette_len as f32 * 0.85) as u8, rng.random_range(10..28))
-                } else {
-                    ((palette_len as f32 * 0.15) as u8, rng.random_range(14..32))
-                }
+            if let Some(p) = spawn_one(&mut rng, profile, kind, palette_len, solid, width, height) {
+                particles.push(p);
             }
-            FireType::ChemicalFire => ((palette_len as f32 * 0.7) as u8, rng.random_range(10..30)),
-            FireType::Cyberpunk => (
-                if rng.random_bool(0.5) {
-                    (palette_len as f32 * 0.85) as u8
-                } else {
-                    (palette_len as f32 * 0.15) as u8
-                },
-                rng.random_range(10..28),
-            ),
-            FireType::Aurora => (
-                rng.random_range((palette_len / 4)..(palette_len * 3 / 4)) as u8,
-                rng.random_range(16..36),
-            ),
-            FireType::Plasma => (
-                if rng.random_bool(0.3) {
-                    (palette_len - 1) as u8
-                } else {
-                    rng.random_range((palette_len / 3)..palette_len) as u8
-                },
-                rng.random_range(10..28),
-            ),
-            FireType::Void => (
-                if rng.random_bool(0.8) {
-                    0
-                } else {
-                    (palette_len as f32 * 0.4) as u8
-                },
-                rng.random_range(18..36),
-            ),
+        }
+    }
+}
+
+/// Creates one particle of `kind` at a random open cell, or `None` if the cell picked is solid
+/// or the grid is too short to spawn into.
+fn spawn_one(
+    rng: &mut ThreadRng,
+    profile: &ParticleProfile,
+    kind: ParticleKind,
+    palette_len: usize,
+    solid: &[bool],
+    width: usize,
+    height: usize,
+) -> Option<Particle> {
+    if height < 3 || width == 0 {
+        return None;
+    }
+    let (min_life, max_life) = profile.life;
+    let px = rng.random_range(0..width);
+    let (py, life) = match kind {
+        ParticleKind::Ember => (height - 2, rng.random_range(min_life..max_life)),
+        ParticleKind::Spark => (height - 2, rng.random_range(min_life / 2..max_life / 2)),
+        // Smoke rises from where the flames thin out
+        ParticleKind::Smoke => (
+            rng.random_range(height / 2..height - 1),
+            rng.random_range(min_life..max_life).saturating_mul(4),
+        ),
+        // Ash drifts down from above the flames
+        ParticleKind::Ash => (
+            rng.random_range(0..height / 2),
+            rng.random_range(min_life..max_life).saturating_mul(4),
+        ),
+    };
+    if solid[py * width + px] {
+        return None;
+    }
+    let color_idx = profile.pick_color(rng, palette_len);
+    Some(Particle::launch(rng, kind, px, py, life, color_idx))
+}
+
+/// Spawns `count` particles of `kind` spread around cell (`x`, `y`), e.g. for a burst of sparks.
+pub fn spawn_burst(
+    particles: &mut Vec<Particle>,
+    kind: ParticleKind,
+    x: usize,
+    y: usize,
+    count: usize,
+    color_idx: u8,
+    width: usize,
+) {
+    let mut rng = ThreadRng::default();
+    for _ in 0..count {
+        let dx = rng.random_range(-4..=4) as isize;
+        let x = (x as isize + dx).clamp(0, width as isize - 1) as usize;
+        let life = match kind {
+            ParticleKind::Spark => rng.random_range(8..20),
+            ParticleKind::Ember => rng.random_range(20..45),
+            ParticleKind::Smoke | ParticleKind::Ash => rng.random_range(40..120),
         };
-        particles.push(Particle {
-            x: px,
-            y: height - 2,
-            life,
-            color_idx,
-        });
+        particles.push(Particle::launch(&mut rng, kind, x, y, life, color_idx));
     }
 }
 
-/// Updates all particles, rendering them to the pixel buffer and moving them.
+/// Updates all particles: integrates buoyancy, drag and wind, then rasterizes each one into its cell.
+/// Heat-carrying kinds burn into `pixel_buffer`, clearing the cell's `heat_fraction`; smoke and ash write
+/// density into `smoke_buffer`.
+/// Particles that leave the grid or run into a solid cell are extinguished.
 pub fn update_particles(
     particles: &mut Vec<Particle>,
     pixel_buffer: &mut [u8],
-    width: usize,
-    _height: usize,
+    heat_fraction: &mut [u8],
+    smoke_buffer: &mut [u8],
+    solid: &[bool],
+    (width, height): (usize, usize),
+    wind: f32,
 ) {
     let mut rng = ThreadRng::default();
     particles.retain_mut(|p| {
-        if p.life > 0 && p.y > 0 {
-            let fade = ((p.life as f32 / 30.0) * (p.color_idx as f32)).max(1.0) as u8;
-            let idx = p.y * width + p.x;
-            if idx < pixel_buffer.len() {
-                pixel_buffer[idx] = fade;
+        if p.life == 0 {
+            return false;
+        }
+        let drag = p.kind.drag();
+        p.vy -= p.kind.buoyancy();
+        p.vx += (wind - p.vx) * WIND_COUPLING + rng.random_range(-TURBULENCE..=TURBULENCE);
+        p.vx *= 1.0 - drag;
+        p.vy *= 1.0 - drag;
+        p.x += p.vx;
+        p.y += p.vy;
+        p.life -= 1;
+
+        if p.x < 0.0 || p.y < 0.0 || p.x >= width as f32 || p.y >= height as f32 {
+            return false;
+        }
+        let idx = p.y as usize * width + p.x as usize;
+        if solid[idx] {
+            return false;
+        }
+        let remaining = p.life as f32 / p.max_life as f32;
+        match p.kind {
+            ParticleKind::Ember => {
+                // Embers hold their glow for most of their life, then fade out
+                let glow = remaining.sqrt();
+                pixel_buffer[idx] = ((glow * p.color_idx as f32).max(1.0)) as u8;
+                heat_fraction[idx] = 0;
+            }
+            ParticleKind::Spark => {
+                for i in 0..SPARK_TRAIL {
+                    let tx = p.x - p.vx * i as f32 * 0.5;
+                    let ty = p.y - p.vy * i as f32 * 0.5;
+                    if tx < 0.0 || ty < 0.0 || tx >= width as f32 || ty >= height as f32 {
+                        break;
+                    }
+                    let tail = 1.0 - i as f32 / SPARK_TRAIL as f32;
+                    let heat = (p.color_idx as f32 * tail).max(1.0) as u8;
+                    let t_idx = ty as usize * width + tx as usize;
+                    if heat > pixel_buffer[t_idx] {
+                        pixel_buffer[t_idx] = heat;
+                        heat_fraction[t_idx] = 0;
+                    }
+                }
+            }
+            ParticleKind::Smoke => {
+                // Puffs thin out as they rise
+                let density = (remaining * 160.0) as u8;
+                smoke_buffer[idx] = smoke_buffer[idx].max(density);
+            }
+            ParticleKind::Ash => {
+                smoke_buffer[idx] = 255;
             }
-            let dx = rng.random_range(0..3) as isize - 1;
-            p.x = (p.x as isize + dx).clamp(0, width as isize - 1) as usize;
-            p.y -= 1;
-            p.life -= 1;
-            true
-        } else {
-            false
         }
+        true
     });
 }
+
+/// Blends a fire colour with the smoke layer. Denser smoke is lighter and more opaque.
+pub fn blend_smoke(color: [u8; 3], density: u8) -> [u8; 3] {
+    if density == 0 {
+        return color;
+    }
+    let d = density as f32 / 255.0;
+    let shade = 60.0 + 100.0 * d;
+    let alpha = 0.7 * d;
+    [
+        (color[0] as f32 * (1.0 - alpha) + shade * alpha) as u8,
+        (color[1] as f32 * (1.0 - alpha) + shade * alpha) as u8,
+        (color[2] as f32 * (1.0 - alpha) + shade * alpha) as u8,
+    ]
+}
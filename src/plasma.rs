@@ -0,0 +1,271 @@
+use crate::config::Config;
+use crate::effect::{brush_cell, for_each_in_disc, Effect, Grid, Reactions};
+use rand::Rng;
+use rayon::prelude::*;
+use std::f32::consts::TAU;
+
+/// Base spatial frequency of the plasma, in radians per cell at `scale = 1.0`.
+const PLASMA_FREQUENCY: f32 = 0.05;
+/// Blob radius in cells at `scale = 1.0`.
+const BLOB_RADIUS: f32 = 22.0;
+/// Field strength at which a blob's edge sits, and how soft that edge is.
+const BLOB_THRESHOLD: f32 = 1.0;
+const BLOB_SOFTNESS: f32 = 0.6;
+
+/// Burned-in and cursor heat, drawn over a pattern that's recomputed every frame and cooling off slowly.
+struct Afterglow {
+    heat: Vec<u8>,
+}
+
+impl Afterglow {
+    fn cool_and_apply(&mut self, target: &mut [u8], step: u8) {
+        for (glow, cell) in self.heat.iter_mut().zip(target.iter_mut()) {
+            *cell = (*cell).max(*glow);
+            *glow = glow.saturating_sub(step);
+        }
+    }
+}
+
+/// Grid, palette and timing shared by the plasma and the lava lamp.
+struct Canvas {
+    grid: Grid,
+    glow: Afterglow,
+    t: f32,
+    speed: f32,
+    intensity: f32,
+    frame_time: f32,
+}
+
+impl Canvas {
+    fn new(config: &Config, speed: Option<f32>) -> Self {
+        let scale = config.scale.unwrap_or(1).max(1);
+        let mut canvas = Self {
+            grid: Grid::new(config),
+            glow: Afterglow { heat: Vec::new() },
+            t: 0.0,
+            speed: speed.unwrap_or(1.0).max(0.0),
+            intensity: 1.0,
+            frame_time: 1.0 / config.fps.unwrap_or(10).max(1) as f32,
+        };
+        canvas.resize(
+            config.screen_width.unwrap_or(1920) / scale,
+            config.screen_height.unwrap_or(1080) / scale,
+        );
+        canvas
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.grid.resize(width, height);
+        self.glow.heat = vec![0; width * height];
+    }
+
+    fn advance(&mut self) -> f32 {
+        self.t += self.frame_time * self.speed * self.intensity;
+        self.t
+    }
+
+    /// Draws the afterglow over the freshly computed pattern and blanks solid cells.
+    fn finish_frame(&mut self) {
+        let step = (self.grid.palette.heat_scale().round() as u8).max(1);
+        self.glow.cool_and_apply(&mut self.grid.heat, step);
+        self.grid.blank_solid();
+    }
+
+    fn clear(&mut self) {
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+        self.glow.heat.iter_mut().for_each(|x| *x = 0);
+    }
+
+    fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+        if x >= self.grid.width || y >= self.grid.height {
+            return;
+        }
+        let idx = y * self.grid.width + x;
+        if !self.grid.solid[idx] {
+            self.glow.heat[idx] = self.glow.heat[idx].max(heat);
+        }
+    }
+
+    fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+        let max_heat = self.grid.palette.colours.len().saturating_sub(1) as u8;
+        for_each_in_disc(self.grid.width, self.grid.height, (x, y), radius, |idx, falloff| {
+            if !self.grid.solid[idx] {
+                brush_cell(&mut self.glow.heat[idx], max_heat, amount, falloff);
+            }
+        });
+    }
+}
+
+/// Forwards the `Effect` methods both effects handle the same way to their `Canvas`.
+macro_rules! canvas_effect_methods {
+    () => {
+        fn grid(&self) -> &Grid {
+            &self.canvas.grid
+        }
+
+        fn grid_mut(&mut self) -> &mut Grid {
+            &mut self.canvas.grid
+        }
+
+        fn clear(&mut self) {
+            self.canvas.clear();
+        }
+
+        /// Burned-in heat glows over the pattern and cools off slowly.
+        fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+            self.canvas.add_heat(x, y, heat);
+        }
+
+        fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+            self.canvas.apply_heat_brush(x, y, radius, amount);
+        }
+    };
+}
+
+/// Classic demoscene sine plasma: overlapping sine waves swept through the palette.
+pub struct Plasma {
+    canvas: Canvas,
+    frequency: f32,
+}
+
+impl Plasma {
+    pub fn new(config: &Config) -> Self {
+        let plasma = config.plasma.as_ref();
+        Self {
+            canvas: Canvas::new(config, plasma.and_then(|p| p.speed)),
+            frequency: PLASMA_FREQUENCY / plasma.and_then(|p| p.scale).unwrap_or(1.0).max(0.05),
+        }
+    }
+}
+
+impl Effect for Plasma {
+    canvas_effect_methods!();
+
+    fn update(&mut self) {
+        let t = self.canvas.advance();
+        let (width, f) = (self.canvas.grid.width, self.frequency);
+        let max_heat = self.canvas.grid.palette.colours.len().saturating_sub(1) as f32;
+        // A moving centre for the radial wave
+        let (cx, cy) = ((t * 0.3).sin() * 40.0, (t * 0.2).cos() * 40.0);
+        self.canvas.grid.heat.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            let fy = y as f32 * f;
+            for (x, cell) in row.iter_mut().enumerate() {
+                let fx = x as f32 * f;
+                let (dx, dy) = (fx - cx * f, fy - cy * f);
+                let v = (fx + t).sin()
+                    + ((fy + t) * 0.5).sin()
+                    + ((fx + fy + t) * 0.5).sin()
+                    + ((dx * dx + dy * dy).sqrt() + t).sin();
+                // Four waves sum to -4..4
+                *cell = ((v + 4.0) / 8.0 * max_heat).round() as u8;
+            }
+        });
+        self.canvas.finish_frame();
+    }
+
+    fn reset(&mut self) {
+        self.canvas.clear();
+        self.canvas.t = 0.0;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.canvas.resize(width, height);
+    }
+}
+
+impl Reactions for Plasma {
+    fn set_intensity(&mut self, intensity: f64) {
+        self.canvas.intensity = intensity.max(0.0) as f32;
+    }
+}
+
+/// One lava lamp blob, bobbing up and down on its own slow cycle.
+struct Blob {
+    x: f32,      // Centre column, 0.0 - 1.0 of the width
+    phase: f32,  // Offset into the rise-and-fall cycle
+    period: f32, // Seconds for one trip up and back down
+    sway: f32,   // Sideways drift, as a fraction of the width
+    radius: f32, // Cells
+}
+
+/// Metaball "lava lamp": soft blobs rise and sink and merge as they pass each other.
+pub struct LavaLamp {
+    canvas: Canvas,
+    blobs: Vec<Blob>,
+    blob_count: usize,
+    radius: f32,
+}
+
+impl LavaLamp {
+    pub fn new(config: &Config) -> Self {
+        let lava = config.lava_lamp.as_ref();
+        let mut lamp = Self {
+            canvas: Canvas::new(config, lava.and_then(|l| l.speed)),
+            blobs: Vec::new(),
+            blob_count: lava.and_then(|l| l.blobs).unwrap_or(8).max(1),
+            radius: BLOB_RADIUS * lava.and_then(|l| l.scale).unwrap_or(1.0).max(0.05),
+        };
+        lamp.reset();
+        lamp
+    }
+}
+
+impl Effect for LavaLamp {
+    canvas_effect_methods!();
+
+    fn update(&mut self) {
+        let t = self.canvas.advance();
+        let (width, height) = (self.canvas.grid.width as f32, self.canvas.grid.height as f32);
+        // Blob centres in cells this frame
+        let centres: Vec<(f32, f32, f32)> = self
+            .blobs
+            .iter()
+            .map(|b| {
+                let cycle = (t / b.period + b.phase) * TAU;
+                let x = (b.x + b.sway * (cycle * 0.5).sin()) * width;
+                // Eased so blobs linger at the top and bottom like warm wax
+                let y = (0.5 + 0.5 * cycle.cos()) * (height + b.radius) - b.radius * 0.5;
+                (x, y, b.radius * b.radius)
+            })
+            .collect();
+        let max_heat = self.canvas.grid.palette.colours.len().saturating_sub(1) as f32;
+        self.canvas.grid.heat.par_chunks_mut(self.canvas.grid.width).enumerate().for_each(|(y, row)| {
+            let fy = y as f32;
+            for (x, cell) in row.iter_mut().enumerate() {
+                let fx = x as f32;
+                let field: f32 = centres
+                    .iter()
+                    .map(|&(bx, by, r2)| r2 / ((fx - bx).powi(2) + (fy - by).powi(2)).max(1.0))
+                    .sum();
+                let v = ((field - BLOB_THRESHOLD + BLOB_SOFTNESS) / (2.0 * BLOB_SOFTNESS)).clamp(0.0, 1.0);
+                *cell = (v * max_heat).round() as u8;
+            }
+        });
+        self.canvas.finish_frame();
+    }
+
+    fn reset(&mut self) {
+        self.canvas.clear();
+        self.canvas.t = 0.0;
+        let mut rng = rand::rng();
+        self.blobs = (0..self.blob_count)
+            .map(|_| Blob {
+                x: rng.random_range(0.1..0.9),
+                phase: rng.random_range(0.0..1.0),
+                period: rng.random_range(12.0..30.0),
+                sway: rng.random_range(0.02..0.08),
+                radius: self.radius * rng.random_range(0.6..1.4),
+            })
+            .collect();
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.canvas.resize(width, height);
+    }
+}
+
+impl Reactions for LavaLamp {
+    fn set_intensity(&mut self, intensity: f64) {
+        self.canvas.intensity = intensity.max(0.0) as f32;
+    }
+}
@@ -0,0 +1,137 @@
+use crate::config::ReactiveConfig;
+use crate::effect::Effect;
+use crate::system_stats::SystemStats;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+const REFRESH: Duration = Duration::from_secs(1);
+const SMOOTHING: f32 = 0.1; // Fraction of the gap to the target closed each frame
+
+/// A system figure that can drive the fire.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    Cpu,  // Usage, 0.0 - 1.0
+    Mem,  // Usage, 0.0 - 1.0
+    Temp, // Degrees Celsius
+    Net,  // Bytes per second
+    Disk, // Bytes per second
+}
+
+impl Metric {
+    fn default_range(self) -> [f32; 2] {
+        match self {
+            Metric::Cpu | Metric::Mem => [0.0, 1.0],
+            Metric::Temp => [40.0, 90.0],
+            Metric::Net => [0.0, 10_000_000.0],
+            Metric::Disk => [0.0, 100_000_000.0],
+        }
+    }
+}
+
+/// A fire setting a metric can drive.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FireParam {
+    Intensity,
+    Wind,
+    Whiteness,
+}
+
+impl FireParam {
+    fn default_range(self) -> [f32; 2] {
+        match self {
+            FireParam::Intensity => [0.5, 1.5],
+            FireParam::Wind => [0.0, 3.0],
+            FireParam::Whiteness => [0.0, 1.0],
+        }
+    }
+}
+
+struct Mapping {
+    metric: Metric,
+    param: FireParam,
+    from: [f32; 2],
+    to: [f32; 2],
+    target: f32,
+    current: f32,
+}
+
+/// Maps system metrics onto fire parameters so the wallpaper doubles as a load monitor.
+pub struct Reactive {
+    stats: SystemStats,
+    mappings: Vec<Mapping>,
+    last_refresh: Option<Instant>,
+    wind_strength: f64, // Configured wind, scaled by wind mappings
+}
+
+impl Reactive {
+    pub fn from_config(configs: &[ReactiveConfig], wind_strength: f64) -> Self {
+        let mappings = configs
+            .iter()
+            .map(|c| {
+                let to = c.to.unwrap_or(c.param.default_range());
+                Mapping {
+                    metric: c.metric,
+                    param: c.param,
+                    from: c.from.unwrap_or(c.metric.default_range()),
+                    to,
+                    target: to[0],
+                    current: to[0],
+                }
+            })
+            .collect();
+        Self {
+            stats: SystemStats::default(),
+            mappings,
+            last_refresh: None,
+            wind_strength,
+        }
+    }
+
+    /// Samples the metrics when due and eases the fire parameters towards their targets.
+    pub fn apply(&mut self, fire: &mut dyn Effect) {
+        if self.last_refresh.is_none_or(|t| t.elapsed() >= REFRESH) {
+            self.last_refresh = Some(Instant::now());
+            self.sample();
+        }
+        for m in &mut self.mappings {
+            m.current += (m.target - m.current) * SMOOTHING;
+            match m.param {
+                FireParam::Intensity => fire.set_intensity(m.current as f64),
+                FireParam::Wind => fire.set_wind_strength(self.wind_strength * m.current as f64),
+                FireParam::Whiteness => fire.set_whiteness(m.current),
+            }
+        }
+    }
+
+    fn sample(&mut self) {
+        // Read each metric once even if several mappings use it; the rate readers keep state between calls
+        let mut values: Vec<(Metric, Option<f32>)> = Vec::new();
+        for m in &self.mappings {
+            if values.iter().any(|(metric, _)| *metric == m.metric) {
+                continue;
+            }
+            let value = match m.metric {
+                Metric::Cpu => self.stats.cpu_usage(),
+                Metric::Mem => self.stats.memory_usage(),
+                Metric::Temp => self.stats.cpu_temperature(),
+                Metric::Net => self.stats.network_throughput(),
+                Metric::Disk => self.stats.disk_throughput(),
+            };
+            values.push((m.metric, value));
+        }
+        for m in &mut self.mappings {
+            let value = values.iter().find(|(metric, _)| *metric == m.metric).and_then(|(_, v)| *v);
+            if let Some(value) = value {
+                let span = m.from[1] - m.from[0];
+                let t = if span.abs() > f32::EPSILON {
+                    ((value - m.from[0]) / span).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                m.target = m.to[0] + (m.to[1] - m.to[0]) * t;
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::config::ScheduleConfig;
+use crate::fire_types::FireType;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use rand::rngs::ThreadRng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+
+/// How often a time-of-day schedule looks at the clock.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How the `[schedule]` table picks fire types.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleMode {
+    /// Steps through `fire_types` in order every `interval` minutes.
+    #[default]
+    Rotate,
+    /// Plays `fire_types` in a random order, showing every one before any repeats.
+    Shuffle,
+    /// Switches at the times of day listed in `[[schedule.times]]`.
+    TimeOfDay,
+}
+
+/// When a time-of-day slot starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SlotStart {
+    At(NaiveTime),
+    Sunrise,
+    Sunset,
+}
+
+impl SlotStart {
+    fn parse(start: &str) -> Result<Self> {
+        match start.trim().to_ascii_lowercase().as_str() {
+            "sunrise" => Ok(SlotStart::Sunrise),
+            "sunset" => Ok(SlotStart::Sunset),
+            time => NaiveTime::parse_from_str(time, "%H:%M")
+                .map(SlotStart::At)
+                .with_context(|| format!("Bad start time '{}', expected HH:MM, sunrise or sunset", start)),
+        }
+    }
+}
+
+struct TimeSlot {
+    start: SlotStart,
+    fire_type: FireType,
+}
+
+/// Changes the fire type over time: a rotating or shuffled playlist, or themes for times of day.
+pub struct Schedule {
+    mode: ScheduleMode,
+    playlist: Vec<FireType>,
+    shuffled: Vec<FireType>, // Still to play this round of a shuffle, popped from the end
+    interval: Duration,
+    slots: Vec<TimeSlot>,
+    location: Option<(f64, f64)>, // Latitude and longitude for sunrise and sunset
+    slot_fire_type: Option<FireType>, // Fire type of the time slot applied last
+    next_change: Instant,
+    rng: ThreadRng,
+}
+
+impl Schedule {
+    pub fn from_config(config: &ScheduleConfig) -> Result<Self> {
+        let playlist = match &config.fire_types {
+            Some(names) => names.iter().map(|name| parse_fire_type(name)).collect::<Result<Vec<_>>>()?,
+            None => FireType::iter().collect(),
+        };
+        if playlist.is_empty() {
+            bail!("fire_types is empty");
+        }
+        let slots = config
+            .times
+            .iter()
+            .flatten()
+            .map(|slot| {
+                Ok(TimeSlot {
+                    start: SlotStart::parse(&slot.start)?,
+                    fire_type: parse_fire_type(&slot.fire_type)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let location = config.latitude.zip(config.longitude);
+        let mode = config.mode.unwrap_or_default();
+        if mode == ScheduleMode::TimeOfDay {
+            if slots.is_empty() {
+                bail!("time_of_day needs at least one [[schedule.times]] entry");
+            }
+            if location.is_none() && slots.iter().any(|s| !matches!(s.start, SlotStart::At(_))) {
+                bail!("sunrise and sunset need latitude and longitude");
+            }
+        }
+        let interval = Duration::from_secs_f32(config.interval.unwrap_or(10.0).max(0.1) * 60.0);
+        Ok(Self {
+            mode,
+            playlist,
+            shuffled: Vec::new(),
+            interval,
+            slots,
+            location,
+            slot_fire_type: None,
+            // Time-of-day themes apply straight away; playlists wait out their first interval
+            next_change: if mode == ScheduleMode::TimeOfDay { Instant::now() } else { Instant::now() + interval },
+            rng: rand::rng(),
+        })
+    }
+
+    /// Called every frame; returns a fire type to switch to when the schedule says it's time.
+    pub fn poll(&mut self, current: FireType) -> Option<FireType> {
+        if Instant::now() < self.next_change {
+            return None;
+        }
+        match self.mode {
+            ScheduleMode::TimeOfDay => {
+                self.next_change = Instant::now() + CHECK_INTERVAL;
+                let target = self.slot_at(Local::now())?;
+                // Only act when the slot changes, so skipping ahead by hand sticks until the next slot
+                if self.slot_fire_type == Some(target) {
+                    return None;
+                }
+                self.slot_fire_type = Some(target);
+                (target != current).then_some(target)
+            }
+            _ => Some(self.next(current)),
+        }
+    }
+
+    /// The playlist entry after `current`, restarting the interval (or the clock check in time-of-day mode).
+    pub fn next(&mut self, current: FireType) -> FireType {
+        let wait = if self.mode == ScheduleMode::TimeOfDay { CHECK_INTERVAL } else { self.interval };
+        self.next_change = Instant::now() + wait;
+        if self.mode != ScheduleMode::Shuffle {
+            return following(&self.playlist, current);
+        }
+        if self.shuffled.is_empty() {
+            self.shuffled = self.playlist.clone();
+            self.shuffled.shuffle(&mut self.rng);
+            // Don't play the same type twice in a row across rounds
+            if self.shuffled.len() > 1 && self.shuffled.last() == Some(&current) {
+                self.shuffled.swap(0, self.playlist.len() - 1);
+            }
+        }
+        self.shuffled.pop().unwrap_or(current)
+    }
+
+    /// The fire type of the latest slot to have started by `now`, wrapping round to yesterday's last slot.
+    fn slot_at(&self, now: DateTime<Local>) -> Option<FireType> {
+        let sun = self.location.and_then(|(lat, lon)| sun_times(now.date_naive(), lat, lon));
+        let starts: Vec<(NaiveTime, FireType)> = self
+            .slots
+            .iter()
+            .filter_map(|slot| {
+                let time = match slot.start {
+                    SlotStart::At(time) => time,
+                    // No sunrise or sunset during polar day or night, so the slot is skipped
+                    SlotStart::Sunrise => sun?.0,
+                    SlotStart::Sunset => sun?.1,
+                };
+                Some((time, slot.fire_type))
+            })
+            .collect();
+        let now = now.time();
+        starts
+            .iter()
+            .filter(|(time, _)| *time <= now)
+            .max_by_key(|(time, _)| *time)
+            .or_else(|| starts.iter().max_by_key(|(time, _)| *time))
+            .map(|&(_, fire_type)| fire_type)
+    }
+}
+
+fn parse_fire_type(name: &str) -> Result<FireType> {
+    FireType::parse(name).with_context(|| format!("Unknown fire type '{}' in [schedule]", name))
+}
+
+/// The entry after `current` in `playlist`, or the first one if `current` isn't in it.
+pub fn following(playlist: &[FireType], current: FireType) -> FireType {
+    match playlist.iter().position(|&t| t == current) {
+        Some(idx) => playlist[(idx + 1) % playlist.len()],
+        None => playlist.first().copied().unwrap_or(current),
+    }
+}
+
+/// Local sunrise and sunset on `date` at `latitude`/`longitude` in degrees (north and east positive),
+/// from the sunrise equation. `None` during polar day or night.
+fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Option<(NaiveTime, NaiveTime)> {
+    let midnight = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp() as f64;
+    let julian_day = (midnight / 86400.0 + 2440587.5 - 2451545.0 + 0.0008).ceil();
+    let mean_noon = julian_day - longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_noon).rem_euclid(360.0).to_radians();
+    let centre = 1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + centre + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = 2451545.0 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * 23.4397f64.to_radians().sin()).asin();
+    let lat = latitude.to_radians();
+    // -0.833° allows for refraction and the size of the sun's disc
+    let cos_hour_angle = ((-0.833f64).to_radians().sin() - lat.sin() * declination.sin()) / (lat.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    let to_local = |julian: f64| {
+        DateTime::from_timestamp(((julian - 2440587.5) * 86400.0) as i64, 0).map(|t| t.with_timezone(&Local).time())
+    };
+    Some((to_local(transit - half_day)?, to_local(transit + half_day)?))
+}
@@ -0,0 +1,184 @@
+use crate::config::Config;
+use crate::effect::{Effect, Grid, Reactions};
+use crate::particle::ParticleKind;
+use crate::perlin::perlin_noise_1d;
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+/// Depth a star travels per second at `speed = 1.0`; the far plane is at 1.0.
+const BASE_SPEED: f32 = 0.35;
+/// Stars closer than this have flown past the viewer.
+const NEAR_PLANE: f32 = 0.02;
+/// Sideways drift per unit of wind strength, in world units per second.
+const WIND_DRIFT: f32 = 0.15;
+
+/// A star in view space: `x` and `y` span -1.0 - 1.0 across the screen at the far plane.
+struct Star {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+/// Flying through a 3D starfield. Stars streak towards the edges and leave trails that cool
+/// through the palette, like the fire does.
+pub struct Starfield {
+    grid: Grid,
+    stars: Vec<Star>,
+    star_count: usize,
+    speed: f32,
+    trail_decay: u8,
+    intensity: f32,
+    wind_strength: f32,
+    gust: f32,
+    t: f64,
+    frame_time: f32,
+    rng: ThreadRng,
+}
+
+impl Starfield {
+    pub fn new(config: &Config) -> Self {
+        let scale = config.scale.unwrap_or(1).max(1);
+        let starfield = config.starfield.as_ref();
+        let mut field = Self {
+            grid: Grid::new(config),
+            stars: Vec::new(),
+            star_count: starfield.and_then(|s| s.stars).unwrap_or(800),
+            speed: starfield.and_then(|s| s.speed).unwrap_or(1.0).max(0.0),
+            trail_decay: starfield.and_then(|s| s.trail).unwrap_or(3).max(1),
+            intensity: 1.0,
+            wind_strength: config.wind_strength.unwrap_or(0.0) as f32,
+            gust: 0.0,
+            t: 0.0,
+            frame_time: 1.0 / config.fps.unwrap_or(10).max(1) as f32,
+            rng: rand::rng(),
+        };
+        field.resize(
+            config.screen_width.unwrap_or(1920) / scale,
+            config.screen_height.unwrap_or(1080) / scale,
+        );
+        field
+    }
+
+    /// A new star somewhere on the far half of the field, or at any depth when filling an empty field.
+    fn random_star(&mut self, anywhere: bool) -> Star {
+        let aspect = self.grid.width as f32 / self.grid.height.max(1) as f32;
+        Star {
+            x: self.rng.random_range(-1.0..1.0) * aspect.max(1.0),
+            y: self.rng.random_range(-1.0..1.0),
+            z: if anywhere { self.rng.random_range(NEAR_PLANE..1.0) } else { self.rng.random_range(0.5..1.0) },
+        }
+    }
+
+    /// Projects a view-space point onto the grid.
+    fn project(&self, x: f32, y: f32, z: f32) -> (f32, f32) {
+        let half_h = self.grid.height as f32 / 2.0;
+        (self.grid.width as f32 / 2.0 + x / z * half_h, half_h + y / z * half_h)
+    }
+
+    /// Draws a streak between two grid points, keeping whatever is already hotter.
+    fn streak(&mut self, from: (f32, f32), to: (f32, f32), value: u8) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let f = i as f32 / steps as f32;
+            let (x, y) = (from.0 + (to.0 - from.0) * f, from.1 + (to.1 - from.1) * f);
+            if x < 0.0 || y < 0.0 || x >= self.grid.width as f32 || y >= self.grid.height as f32 {
+                continue;
+            }
+            let idx = y as usize * self.grid.width + x as usize;
+            if !self.grid.solid[idx] {
+                self.grid.heat[idx] = self.grid.heat[idx].max(value);
+            }
+        }
+    }
+}
+
+impl Effect for Starfield {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    fn update(&mut self) {
+        let decay = ((self.trail_decay as f32 * self.grid.palette.heat_scale()).round() as u8).max(1);
+        self.grid.heat.iter_mut().for_each(|h| *h = h.saturating_sub(decay));
+
+        self.t += 0.03;
+        let dt = self.frame_time;
+        let dz = BASE_SPEED * self.speed * self.intensity * (1.0 + self.gust) * dt;
+        self.gust *= 0.9;
+        // Wind wanders like it does for the fire, swaying the whole field sideways
+        let drift = perlin_noise_1d(self.t) as f32 * self.wind_strength * WIND_DRIFT * dt;
+        let max_heat = self.max_heat() as f32;
+
+        let mut stars = std::mem::take(&mut self.stars);
+        let mut count = stars.len();
+        stars.retain_mut(|star| {
+            let before = self.project(star.x, star.y, star.z);
+            star.z -= dz;
+            star.x += drift;
+            let after = self.project(star.x, star.y, star.z.max(NEAR_PLANE));
+            let off_screen = after.0 < 0.0 || after.1 < 0.0 || after.0 >= self.grid.width as f32 || after.1 >= self.grid.height as f32;
+            if star.z <= NEAR_PLANE || off_screen {
+                // Extra stars from bursts aren't replaced once they've flown past
+                if count > self.star_count {
+                    count -= 1;
+                    return false;
+                }
+                *star = self.random_star(false);
+                return true;
+            }
+            // Nearer stars burn hotter
+            let value = (max_heat * (1.0 - star.z).powi(2)).max(max_heat / 4.0) as u8;
+            self.streak(before, after, value);
+            true
+        });
+        self.stars = stars;
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+        self.stars = (0..self.star_count).map(|_| self.random_star(true)).collect();
+    }
+
+    fn clear(&mut self) {
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.grid.resize(width, height);
+        self.reset();
+    }
+}
+
+impl Reactions for Starfield {
+    /// Throws a few stars out from (`x`, `y`) at close range.
+    fn spawn_burst(&mut self, _kind: ParticleKind, x: usize, y: usize, count: usize) {
+        let half_h = self.grid.height as f32 / 2.0;
+        let z = 0.3;
+        for _ in 0..count {
+            let (jx, jy) = (self.rng.random_range(-2.0..2.0), self.rng.random_range(-2.0..2.0));
+            let star = Star {
+                x: (x as f32 + jx - self.grid.width as f32 / 2.0) / half_h * z,
+                y: (y as f32 + jy - half_h) / half_h * z,
+                z,
+            };
+            self.stars.push(star);
+        }
+    }
+
+    /// A gust kicks the field into warp for a moment.
+    fn gust(&mut self, strength: f64) {
+        self.gust = strength.abs() as f32;
+    }
+
+    fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity.max(0.0) as f32;
+    }
+
+    fn set_wind_strength(&mut self, wind_strength: f64) {
+        self.wind_strength = wind_strength as f32;
+    }
+}
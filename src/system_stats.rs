@@ -0,0 +1,506 @@
+use crate::config::StatsConfig;
+use crate::text::{stamp_text, text_height, text_width, Anchor, Placement};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Reads CPU, memory, temperature and I/O figures from procfs and sysfs.
+/// The roots can be pointed at a fixture directory instead of the live system.
+pub struct SystemStats {
+    proc_dir: PathBuf,
+    sys_dir: PathBuf,
+    last_cpu: Option<(u64, u64)>, // (idle, total) jiffies from the previous sample
+    last_net: Option<(u64, Instant)>,
+    last_disk: Option<(u64, Instant)>,
+}
+
+impl Default for SystemStats {
+    fn default() -> Self {
+        Self::with_roots("/proc", "/sys")
+    }
+}
+
+impl SystemStats {
+    /// Reads `stat`, `meminfo`, `net/dev` and `diskstats` under `proc_dir`, and `class/thermal`, `block`
+    /// and `devices/virtual` under `sys_dir`.
+    pub fn with_roots(proc_dir: impl Into<PathBuf>, sys_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_dir: proc_dir.into(),
+            sys_dir: sys_dir.into(),
+            last_cpu: None,
+            last_net: None,
+            last_disk: None,
+        }
+    }
+
+    /// Returns CPU usage in 0.0..=1.0 since the previous call.
+    /// The first call measures since boot, as there is no earlier sample.
+    pub fn cpu_usage(&mut self) -> Option<f32> {
+        let stat = fs::read_to_string(self.proc_dir.join("stat")).ok()?;
+        let fields: Vec<u64> = stat
+            .lines()
+            .find(|l| l.starts_with("cpu "))?
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        if fields.len() < 4 {
+            return None;
+        }
+        // user nice system idle iowait irq softirq steal; guest time is already counted in user
+        let total: u64 = fields.iter().take(8).sum();
+        let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+        let (prev_idle, prev_total) = self.last_cpu.replace((idle, total)).unwrap_or((0, 0));
+        let d_total = total.saturating_sub(prev_total);
+        if d_total == 0 {
+            return Some(0.0);
+        }
+        let d_idle = idle.saturating_sub(prev_idle);
+        Some(1.0 - d_idle as f32 / d_total as f32)
+    }
+
+    /// Returns memory in use as a fraction of total memory.
+    pub fn memory_usage(&self) -> Option<f32> {
+        let meminfo = fs::read_to_string(self.proc_dir.join("meminfo")).ok()?;
+        let field = |name: &str| -> Option<u64> {
+            meminfo
+                .lines()
+                .find(|l| l.starts_with(name))?
+                .split_whitespace()
+                .nth(1)?
+                .parse()
+                .ok()
+        };
+        let total = field("MemTotal:")?;
+        let available = field("MemAvailable:")?;
+        if total == 0 {
+            return None;
+        }
+        Some(total.saturating_sub(available) as f32 / total as f32)
+    }
+
+    /// Returns the CPU temperature in degrees Celsius.
+    /// Prefers a thermal zone whose type names the CPU package, otherwise the first readable zone.
+    pub fn cpu_temperature(&self) -> Option<f32> {
+        let mut zones: Vec<PathBuf> = fs::read_dir(self.sys_dir.join("class/thermal"))
+            .ok()?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("thermal_zone"))
+            })
+            .collect();
+        zones.sort();
+        let is_cpu = |zone: &Path| {
+            fs::read_to_string(zone.join("type"))
+                .map(|t| {
+                    let t = t.trim();
+                    t.contains("x86_pkg_temp") || t.contains("cpu") || t.contains("k10temp")
+                })
+                .unwrap_or(false)
+        };
+        let zone = zones.iter().find(|z| is_cpu(z)).or(zones.first())?;
+        let millis: f32 = fs::read_to_string(zone.join("temp")).ok()?.trim().parse().ok()?;
+        Some(millis / 1000.0)
+    }
+
+    /// Returns bytes per second received plus sent on physical interfaces, averaged since the previous call.
+    /// Loopback, bridges, veths and other virtual interfaces would count the same traffic again, so they're skipped.
+    /// The first call only takes a baseline and returns zero.
+    pub fn network_throughput(&mut self) -> Option<f32> {
+        let dev = fs::read_to_string(self.proc_dir.join("net/dev")).ok()?;
+        let virtual_net = self.sys_dir.join("devices/virtual/net");
+        let total: u64 = dev
+            .lines()
+            .filter_map(|l| l.split_once(':'))
+            .filter(|(name, _)| name.trim() != "lo" && !virtual_net.join(name.trim()).exists())
+            .map(|(_, values)| {
+                let fields: Vec<u64> = values.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+                // Receive bytes is the first column, transmit bytes the ninth
+                fields.first().copied().unwrap_or(0) + fields.get(8).copied().unwrap_or(0)
+            })
+            .sum();
+        Some(rate(&mut self.last_net, total))
+    }
+
+    /// Returns bytes per second read plus written on whole disks, averaged since the previous call.
+    /// The first call only takes a baseline and returns zero.
+    pub fn disk_throughput(&mut self) -> Option<f32> {
+        let diskstats = fs::read_to_string(self.proc_dir.join("diskstats")).ok()?;
+        let sectors: u64 = diskstats
+            .lines()
+            .filter_map(|l| {
+                let fields: Vec<&str> = l.split_whitespace().collect();
+                let name = *fields.get(2)?;
+                if is_virtual_or_partition(&self.sys_dir, name) {
+                    return None;
+                }
+                // Sectors read and sectors written are the 6th and 10th columns
+                let read: u64 = fields.get(5)?.parse().ok()?;
+                let written: u64 = fields.get(9)?.parse().ok()?;
+                Some(read + written)
+            })
+            .sum();
+        // The kernel always reports diskstats in 512-byte sectors
+        Some(rate(&mut self.last_disk, sectors * 512))
+    }
+}
+
+/// Updates `last` with a new counter reading and returns the per-second rate since the old one.
+fn rate(last: &mut Option<(u64, Instant)>, counter: u64) -> f32 {
+    let now = Instant::now();
+    match last.replace((counter, now)) {
+        Some((prev, at)) => {
+            let secs = now.duration_since(at).as_secs_f32();
+            if secs > 0.0 {
+                counter.saturating_sub(prev) as f32 / secs
+            } else {
+                0.0
+            }
+        }
+        None => 0.0,
+    }
+}
+
+/// Skips virtual devices and partitions so bytes aren't counted twice. Whole disks are the entries of
+/// `block` that aren't under `devices/virtual/block`; without a `block` directory, goes by the name.
+fn is_virtual_or_partition(sys_dir: &Path, name: &str) -> bool {
+    let block = sys_dir.join("block");
+    if block.is_dir() {
+        return !block.join(name).exists() || sys_dir.join("devices/virtual/block").join(name).exists();
+    }
+    if name.starts_with("loop") || name.starts_with("ram") || name.starts_with("zram") {
+        return true;
+    }
+    let ends_in_digit = name.chars().last().is_some_and(|c| c.is_ascii_digit());
+    if name.starts_with("nvme") || name.starts_with("mmcblk") {
+        // nvme0n1p2, mmcblk0p1
+        return name
+            .rsplit_once('p')
+            .is_some_and(|(_, n)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    }
+    ends_in_digit && ["sd", "hd", "vd", "xvd"].iter().any(|p| name.starts_with(p))
+}
+
+/// A figure shown by the stats overlay.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatItem {
+    Cpu,
+    Mem,
+    Temp,
+}
+
+/// Stacks items on separate lines or joins them on one line.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsLayout {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+/// Burns system figures into the fire with the same bitmap font as the FPS counter.
+pub struct StatsOverlay {
+    stats: SystemStats,
+    items: Vec<StatItem>,
+    layout: StatsLayout,
+    anchor: Anchor,
+    offset_x: isize,
+    offset_y: isize,
+    size: usize,
+    refresh: Duration,
+    last_refresh: Option<Instant>,
+    text: String,
+}
+
+impl StatsOverlay {
+    /// Builds the overlay from config. Offsets are given in screen pixels and mapped onto the fire grid.
+    pub fn from_config(config: &StatsConfig, scale: usize) -> Self {
+        let scale = scale.max(1) as isize;
+        Self {
+            stats: SystemStats::default(),
+            items: config
+                .items
+                .clone()
+                .unwrap_or_else(|| vec![StatItem::Cpu, StatItem::Mem, StatItem::Temp]),
+            layout: config.layout.unwrap_or_default(),
+            anchor: config.anchor.unwrap_or(Anchor::TopLeft),
+            offset_x: config.offset_x.unwrap_or(20) as isize / scale,
+            offset_y: config.offset_y.unwrap_or(80) as isize / scale,
+            size: config.size.unwrap_or(1).max(1),
+            refresh: Duration::from_millis(config.refresh_ms.unwrap_or(1000)),
+            last_refresh: None,
+            text: String::new(),
+        }
+    }
+
+    /// Re-reads the figures if the refresh interval has passed.
+    pub fn refresh(&mut self) {
+        if self.last_refresh.is_some_and(|t| t.elapsed() < self.refresh) {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+        let parts: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| match item {
+                StatItem::Cpu => format_stat("CPU", self.stats.cpu_usage().map(|v| v * 100.0), "%"),
+                StatItem::Mem => format_stat("MEM", self.stats.memory_usage().map(|v| v * 100.0), "%"),
+                StatItem::Temp => format_stat("TMP", self.stats.cpu_temperature(), "C"),
+            })
+            .collect();
+        let separator = match self.layout {
+            StatsLayout::Vertical => "\n",
+            StatsLayout::Horizontal => "  ",
+        };
+        self.text = parts.join(separator);
+    }
+
+    pub fn stamp(&self, buffer: &mut [u8], width: usize, height: usize, value: u8) {
+        let (x, y) = self.anchor.place(
+            text_width(&self.text, self.size),
+            text_height(&self.text, self.size),
+            width,
+            height,
+            self.offset_x,
+            self.offset_y,
+        );
+        stamp_text(buffer, width, height, &self.text, Placement { x, y, size: self.size }, value);
+    }
+}
+
+fn format_stat(label: &str, value: Option<f32>, unit: &str) -> String {
+    match value {
+        Some(v) => format!("{} {:.0}{}", label, v, unit),
+        None => format!("{} --", label),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch directory with `proc` and `sys` roots, removed on drop.
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("doomfire-stats-{}-{}", name, std::process::id()));
+            fs::create_dir_all(root.join("proc")).unwrap();
+            fs::create_dir_all(root.join("sys/class/thermal")).unwrap();
+            Self { root }
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            let path = self.root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        fn stats(&self) -> SystemStats {
+            SystemStats::with_roots(self.root.join("proc"), self.root.join("sys"))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn assert_near(value: Option<f32>, expected: f32) {
+        let value = value.expect("no reading");
+        assert!((value - expected).abs() < 1e-4, "got {}, expected {}", value, expected);
+    }
+
+    /// Rows of `/proc/net/dev` for (interface, received bytes, transmitted bytes).
+    fn net_dev(rows: &[(&str, u64, u64)]) -> String {
+        let mut dev = String::from("Inter-|   Receive                |  Transmit\n face |bytes    packets errs drop|bytes    packets errs drop\n");
+        for (name, rx, tx) in rows {
+            dev.push_str(&format!("{:>6}: {} 10 0 0 0 0 0 0 {} 10 0 0 0 0 0 0\n", name, rx, tx));
+        }
+        dev
+    }
+
+    /// Rows of `/proc/diskstats` for (device, sectors read, sectors written).
+    fn diskstats(rows: &[(&str, u64, u64)]) -> String {
+        rows.iter()
+            .map(|(name, read, written)| format!("   8       0 {} 50 0 {} 40 30 0 {} 20 0 60 60 0 0 0 0 0 0\n", name, read, written))
+            .collect()
+    }
+
+    /// Checks a second reading, taken after `update` and a short pause, against `delta` bytes over the pause.
+    fn assert_rate(mut read: impl FnMut() -> Option<f32>, update: impl FnOnce(), delta: f32) {
+        let start = Instant::now();
+        assert_eq!(read(), Some(0.0), "the first reading is only a baseline");
+        let first = Instant::now();
+        update();
+        std::thread::sleep(Duration::from_millis(20));
+        let second = Instant::now();
+        let value = read().expect("no reading");
+        let end = Instant::now();
+        // The reader's own timestamps fall somewhere between these
+        let (slowest, fastest) = (delta / (end - start).as_secs_f32(), delta / (second - first).as_secs_f32());
+        assert!(value >= slowest && value <= fastest, "got {}, expected {} - {}", value, slowest, fastest);
+    }
+
+    #[test]
+    fn cpu_usage_between_samples() {
+        let fixture = Fixture::new("cpu");
+        let mut stats = fixture.stats();
+        // user nice system idle iowait ...: 800 of 1000 jiffies idle since boot
+        fixture.write("proc/stat", "cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 100 0 100 700 100 0 0 0 0 0\n");
+        assert_near(stats.cpu_usage(), 0.2);
+        // 700 of the next 1000 jiffies idle
+        fixture.write("proc/stat", "cpu  300 0 200 1300 200 0 0 0 0 0\n");
+        assert_near(stats.cpu_usage(), 0.3);
+        // No time passed
+        assert_near(stats.cpu_usage(), 0.0);
+    }
+
+    #[test]
+    fn memory_usage_from_meminfo() {
+        let fixture = Fixture::new("mem");
+        fixture.write("proc/meminfo", "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    4000000 kB\n");
+        assert_near(fixture.stats().memory_usage(), 0.75);
+    }
+
+    #[test]
+    fn cpu_temperature_prefers_the_cpu_zone() {
+        let fixture = Fixture::new("temp");
+        fixture.write("sys/class/thermal/thermal_zone0/type", "acpitz\n");
+        fixture.write("sys/class/thermal/thermal_zone0/temp", "40000\n");
+        // Only thermal zones count, however CPU-like a cooling device's type
+        fixture.write("sys/class/thermal/cooling_device0/type", "cpu-cooling\n");
+        assert_near(fixture.stats().cpu_temperature(), 40.0);
+        fixture.write("sys/class/thermal/thermal_zone1/type", "x86_pkg_temp\n");
+        fixture.write("sys/class/thermal/thermal_zone1/temp", "55500\n");
+        assert_near(fixture.stats().cpu_temperature(), 55.5);
+    }
+
+    #[test]
+    fn missing_files_read_as_none() {
+        let fixture = Fixture::new("missing");
+        let mut stats = fixture.stats();
+        assert_eq!(stats.cpu_usage(), None);
+        assert_eq!(stats.memory_usage(), None);
+        assert_eq!(stats.cpu_temperature(), None);
+        let mut stats = SystemStats::with_roots(fixture.root.join("nowhere"), fixture.root.join("nowhere"));
+        assert_eq!(stats.cpu_usage(), None);
+        assert_eq!(stats.cpu_temperature(), None);
+    }
+
+    #[test]
+    fn garbled_files_read_as_none() {
+        let fixture = Fixture::new("garbled");
+        fixture.write("proc/stat", "cpu  lots of time\n");
+        fixture.write("proc/meminfo", "MemTotal:       16000000 kB\n");
+        fixture.write("sys/class/thermal/thermal_zone0/type", "x86_pkg_temp\n");
+        fixture.write("sys/class/thermal/thermal_zone0/temp", "hot\n");
+        let mut stats = fixture.stats();
+        assert_eq!(stats.cpu_usage(), None);
+        assert_eq!(stats.memory_usage(), None);
+        assert_eq!(stats.cpu_temperature(), None);
+        fixture.write("proc/meminfo", "MemTotal: 0 kB\nMemAvailable: 0 kB\n");
+        assert_eq!(stats.memory_usage(), None);
+    }
+
+    #[test]
+    fn network_throughput_skips_loopback_and_virtual_interfaces() {
+        let fixture = Fixture::new("net");
+        fs::create_dir_all(fixture.root.join("sys/devices/virtual/net/docker0")).unwrap();
+        fs::create_dir_all(fixture.root.join("sys/devices/virtual/net/veth1a2b")).unwrap();
+        let mut stats = fixture.stats();
+        fixture.write(
+            "proc/net/dev",
+            &net_dev(&[("lo", 1000, 1000), ("eth0", 5000, 3000), ("wlan0", 100, 100), ("docker0", 7000, 7000), ("veth1a2b", 0, 0)]),
+        );
+        let update = || {
+            fixture.write(
+                "proc/net/dev",
+                &net_dev(&[
+                    ("lo", 900_000, 900_000),
+                    ("eth0", 9000, 5000),
+                    ("wlan0", 1100, 100),
+                    ("docker0", 800_000, 800_000),
+                    ("veth1a2b", 800_000, 800_000),
+                ]),
+            )
+        };
+        assert_rate(|| stats.network_throughput(), update, 7000.0);
+    }
+
+    #[test]
+    fn counters_that_go_backwards_read_as_zero() {
+        let fixture = Fixture::new("net-reset");
+        let mut stats = fixture.stats();
+        fixture.write("proc/net/dev", &net_dev(&[("eth0", 5000, 3000)]));
+        assert_eq!(stats.network_throughput(), Some(0.0));
+        // The interface was recreated and its counters started over
+        fixture.write("proc/net/dev", &net_dev(&[("eth0", 10, 10)]));
+        assert_eq!(stats.network_throughput(), Some(0.0));
+    }
+
+    #[test]
+    fn disk_throughput_counts_whole_disks_only() {
+        let fixture = Fixture::new("disk");
+        for disk in ["sda", "nvme0n1", "loop0", "dm-0"] {
+            fs::create_dir_all(fixture.root.join("sys/block").join(disk)).unwrap();
+        }
+        for disk in ["loop0", "dm-0"] {
+            fs::create_dir_all(fixture.root.join("sys/devices/virtual/block").join(disk)).unwrap();
+        }
+        let mut stats = fixture.stats();
+        fixture.write(
+            "proc/diskstats",
+            &diskstats(&[("sda", 100, 200), ("sda1", 100, 200), ("nvme0n1", 50, 50), ("nvme0n1p1", 50, 50), ("loop0", 10, 0), ("dm-0", 40, 40)]),
+        );
+        let update = || {
+            fixture.write(
+                "proc/diskstats",
+                &diskstats(&[
+                    ("sda", 110, 206),
+                    ("sda1", 110, 206),
+                    ("nvme0n1", 50, 54),
+                    ("nvme0n1p1", 50, 54),
+                    ("loop0", 5000, 0),
+                    ("dm-0", 5000, 5000),
+                ]),
+            )
+        };
+        // 20 sectors of 512 bytes on the two whole disks
+        assert_rate(|| stats.disk_throughput(), update, 20.0 * 512.0);
+    }
+
+    #[test]
+    fn partitions_and_virtual_devices_from_sys_block() {
+        let fixture = Fixture::new("block");
+        let sys = fixture.root.join("sys");
+        for disk in ["sda", "mmcblk0", "md0", "loop0"] {
+            fs::create_dir_all(sys.join("block").join(disk)).unwrap();
+        }
+        fs::create_dir_all(sys.join("devices/virtual/block/loop0")).unwrap();
+        assert!(!is_virtual_or_partition(&sys, "sda"));
+        assert!(!is_virtual_or_partition(&sys, "mmcblk0"));
+        assert!(!is_virtual_or_partition(&sys, "md0"));
+        assert!(is_virtual_or_partition(&sys, "sda1"));
+        assert!(is_virtual_or_partition(&sys, "mmcblk0p1"));
+        assert!(is_virtual_or_partition(&sys, "loop0"));
+    }
+
+    #[test]
+    fn partitions_and_virtual_devices_by_name_without_sys_block() {
+        let sys = Path::new("/nonexistent");
+        for disk in ["sda", "vdb", "nvme0n1", "mmcblk0"] {
+            assert!(!is_virtual_or_partition(sys, disk), "{} is a whole disk", disk);
+        }
+        for disk in ["sda1", "vdb2", "nvme0n1p2", "mmcblk0p1", "loop3", "ram0", "zram0"] {
+            assert!(is_virtual_or_partition(sys, disk), "{} is a partition or virtual", disk);
+        }
+    }
+}
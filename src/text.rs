@@ -0,0 +1,257 @@
+use crate::config::TextConfig;
+use serde::Deserialize;
+
+pub const CHAR_WIDTH: usize = 3;
+pub const CHAR_HEIGHT: usize = 5;
+const SPACING: usize = 1;
+
+/// 3x5 bitmap font covering printable ASCII (' ' to '~').
+const FONT: [[[u8; 3]; 5]; 95] = [
+    [[0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]], // space
+    [[0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 0, 0], [0, 1, 0]], // !
+    [[1, 0, 1], [1, 0, 1], [0, 0, 0], [0, 0, 0], [0, 0, 0]], // "
+    [[1, 0, 1], [1, 1, 1], [1, 0, 1], [1, 1, 1], [1, 0, 1]], // #
+    [[0, 1, 1], [1, 1, 0], [0, 1, 0], [0, 1, 1], [1, 1, 0]], // $
+    [[1, 0, 1], [0, 0, 1], [0, 1, 0], [1, 0, 0], [1, 0, 1]], // %
+    [[0, 1, 0], [1, 0, 1], [0, 1, 0], [1, 0, 1], [0, 1, 1]], // &
+    [[0, 1, 0], [0, 1, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]], // '
+    [[0, 0, 1], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 0, 1]], // (
+    [[1, 0, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [1, 0, 0]], // )
+    [[0, 0, 0], [1, 0, 1], [0, 1, 0], [1, 0, 1], [0, 0, 0]], // *
+    [[0, 0, 0], [0, 1, 0], [1, 1, 1], [0, 1, 0], [0, 0, 0]], // +
+    [[0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 1, 0], [1, 0, 0]], // ,
+    [[0, 0, 0], [0, 0, 0], [1, 1, 1], [0, 0, 0], [0, 0, 0]], // -
+    [[0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 1, 0]], // .
+    [[0, 0, 1], [0, 0, 1], [0, 1, 0], [1, 0, 0], [1, 0, 0]], // /
+    [[1, 1, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 1, 1]], // 0
+    [[0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0]], // 1
+    [[1, 1, 1], [0, 0, 1], [1, 1, 1], [1, 0, 0], [1, 1, 1]], // 2
+    [[1, 1, 1], [0, 0, 1], [1, 1, 1], [0, 0, 1], [1, 1, 1]], // 3
+    [[1, 0, 1], [1, 0, 1], [1, 1, 1], [0, 0, 1], [0, 0, 1]], // 4
+    [[1, 1, 1], [1, 0, 0], [1, 1, 1], [0, 0, 1], [1, 1, 1]], // 5
+    [[1, 1, 1], [1, 0, 0], [1, 1, 1], [1, 0, 1], [1, 1, 1]], // 6
+    [[1, 1, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1]], // 7
+    [[1, 1, 1], [1, 0, 1], [1, 1, 1], [1, 0, 1], [1, 1, 1]], // 8
+    [[1, 1, 1], [1, 0, 1], [1, 1, 1], [0, 0, 1], [1, 1, 1]], // 9
+    [[0, 0, 0], [0, 1, 0], [0, 0, 0], [0, 1, 0], [0, 0, 0]], // :
+    [[0, 0, 0], [0, 1, 0], [0, 0, 0], [0, 1, 0], [1, 0, 0]], // ;
+    [[0, 0, 1], [0, 1, 0], [1, 0, 0], [0, 1, 0], [0, 0, 1]], // <
+    [[0, 0, 0], [1, 1, 1], [0, 0, 0], [1, 1, 1], [0, 0, 0]], // =
+    [[1, 0, 0], [0, 1, 0], [0, 0, 1], [0, 1, 0], [1, 0, 0]], // >
+    [[1, 1, 1], [0, 0, 1], [0, 1, 0], [0, 0, 0], [0, 1, 0]], // ?
+    [[0, 1, 0], [1, 0, 1], [1, 1, 1], [1, 0, 0], [0, 1, 1]], // @
+    [[0, 1, 0], [1, 0, 1], [1, 1, 1], [1, 0, 1], [1, 0, 1]], // A
+    [[1, 1, 0], [1, 0, 1], [1, 1, 0], [1, 0, 1], [1, 1, 0]], // B
+    [[0, 1, 1], [1, 0, 0], [1, 0, 0], [1, 0, 0], [0, 1, 1]], // C
+    [[1, 1, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 1, 0]], // D
+    [[1, 1, 1], [1, 0, 0], [1, 1, 0], [1, 0, 0], [1, 1, 1]], // E
+    [[1, 1, 1], [1, 0, 0], [1, 1, 0], [1, 0, 0], [1, 0, 0]], // F
+    [[0, 1, 1], [1, 0, 0], [1, 0, 1], [1, 0, 1], [0, 1, 1]], // G
+    [[1, 0, 1], [1, 0, 1], [1, 1, 1], [1, 0, 1], [1, 0, 1]], // H
+    [[1, 1, 1], [0, 1, 0], [0, 1, 0], [0, 1, 0], [1, 1, 1]], // I
+    [[0, 0, 1], [0, 0, 1], [0, 0, 1], [1, 0, 1], [0, 1, 0]], // J
+    [[1, 0, 1], [1, 0, 1], [1, 1, 0], [1, 0, 1], [1, 0, 1]], // K
+    [[1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 1, 1]], // L
+    [[1, 0, 1], [1, 1, 1], [1, 1, 1], [1, 0, 1], [1, 0, 1]], // M
+    [[1, 1, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1]], // N
+    [[0, 1, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1], [0, 1, 0]], // O
+    [[1, 1, 0], [1, 0, 1], [1, 1, 0], [1, 0, 0], [1, 0, 0]], // P
+    [[0, 1, 0], [1, 0, 1], [1, 0, 1], [1, 1, 0], [0, 1, 1]], // Q
+    [[1, 1, 0], [1, 0, 1], [1, 1, 0], [1, 0, 1], [1, 0, 1]], // R
+    [[0, 1, 1], [1, 0, 0], [0, 1, 0], [0, 0, 1], [1, 1, 0]], // S
+    [[1, 1, 1], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0]], // T
+    [[1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 1, 1]], // U
+    [[1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [0, 1, 0]], // V
+    [[1, 0, 1], [1, 0, 1], [1, 1, 1], [1, 1, 1], [1, 0, 1]], // W
+    [[1, 0, 1], [1, 0, 1], [0, 1, 0], [1, 0, 1], [1, 0, 1]], // X
+    [[1, 0, 1], [1, 0, 1], [0, 1, 0], [0, 1, 0], [0, 1, 0]], // Y
+    [[1, 1, 1], [0, 0, 1], [0, 1, 0], [1, 0, 0], [1, 1, 1]], // Z
+    [[1, 1, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 1, 0]], // [
+    [[1, 0, 0], [1, 0, 0], [0, 1, 0], [0, 0, 1], [0, 0, 1]], // backslash
+    [[0, 1, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 1, 1]], // ]
+    [[0, 1, 0], [1, 0, 1], [0, 0, 0], [0, 0, 0], [0, 0, 0]], // ^
+    [[0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0], [1, 1, 1]], // _
+    [[1, 0, 0], [0, 1, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]], // `
+    [[0, 0, 0], [1, 1, 0], [0, 1, 1], [1, 0, 1], [1, 1, 1]], // a
+    [[1, 0, 0], [1, 1, 0], [1, 0, 1], [1, 0, 1], [1, 1, 0]], // b
+    [[0, 0, 0], [0, 1, 1], [1, 0, 0], [1, 0, 0], [0, 1, 1]], // c
+    [[0, 0, 1], [0, 1, 1], [1, 0, 1], [1, 0, 1], [0, 1, 1]], // d
+    [[0, 0, 0], [0, 1, 0], [1, 1, 1], [1, 0, 0], [0, 1, 1]], // e
+    [[0, 0, 1], [0, 1, 0], [1, 1, 1], [0, 1, 0], [0, 1, 0]], // f
+    [[0, 1, 1], [1, 0, 1], [0, 1, 1], [0, 0, 1], [1, 1, 0]], // g
+    [[1, 0, 0], [1, 1, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1]], // h
+    [[0, 1, 0], [0, 0, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0]], // i
+    [[0, 0, 1], [0, 0, 0], [0, 0, 1], [1, 0, 1], [0, 1, 0]], // j
+    [[1, 0, 0], [1, 0, 1], [1, 1, 0], [1, 1, 0], [1, 0, 1]], // k
+    [[1, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [1, 1, 1]], // l
+    [[0, 0, 0], [1, 1, 1], [1, 1, 1], [1, 0, 1], [1, 0, 1]], // m
+    [[0, 0, 0], [1, 1, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1]], // n
+    [[0, 0, 0], [0, 1, 0], [1, 0, 1], [1, 0, 1], [0, 1, 0]], // o
+    [[0, 0, 0], [1, 1, 0], [1, 0, 1], [1, 1, 0], [1, 0, 0]], // p
+    [[0, 0, 0], [0, 1, 1], [1, 0, 1], [0, 1, 1], [0, 0, 1]], // q
+    [[0, 0, 0], [0, 1, 1], [1, 0, 0], [1, 0, 0], [1, 0, 0]], // r
+    [[0, 0, 0], [0, 1, 1], [1, 1, 0], [0, 1, 1], [1, 1, 0]], // s
+    [[0, 1, 0], [1, 1, 1], [0, 1, 0], [0, 1, 0], [0, 1, 1]], // t
+    [[0, 0, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1], [0, 1, 1]], // u
+    [[0, 0, 0], [1, 0, 1], [1, 0, 1], [1, 0, 1], [0, 1, 0]], // v
+    [[0, 0, 0], [1, 0, 1], [1, 0, 1], [1, 1, 1], [1, 1, 1]], // w
+    [[0, 0, 0], [1, 0, 1], [0, 1, 0], [0, 1, 0], [1, 0, 1]], // x
+    [[1, 0, 1], [1, 0, 1], [0, 1, 1], [0, 0, 1], [1, 1, 0]], // y
+    [[0, 0, 0], [1, 1, 1], [0, 1, 1], [1, 1, 0], [1, 1, 1]], // z
+    [[0, 1, 1], [0, 1, 0], [1, 1, 0], [0, 1, 0], [0, 1, 1]], // {
+    [[0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0]], // |
+    [[1, 1, 0], [0, 1, 0], [0, 1, 1], [0, 1, 0], [1, 1, 0]], // }
+    [[0, 0, 0], [0, 1, 1], [1, 1, 0], [0, 0, 0], [0, 0, 0]], // ~
+];
+
+/// Where an overlay is placed on the fire grid.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    #[default]
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Returns the top-left cell for a block of `block_w` x `block_h` cells inside a `width` x `height` grid.
+    /// Offsets push the block away from the anchored edge; on centred axes they move it right/down.
+    pub fn place(
+        self,
+        block_w: usize,
+        block_h: usize,
+        width: usize,
+        height: usize,
+        offset_x: isize,
+        offset_y: isize,
+    ) -> (usize, usize) {
+        let free_w = width.saturating_sub(block_w) as isize;
+        let free_h = height.saturating_sub(block_h) as isize;
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => offset_x,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => free_w / 2 + offset_x,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => free_w - offset_x,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => offset_y,
+            Anchor::Left | Anchor::Center | Anchor::Right => free_h / 2 + offset_y,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => free_h - offset_y,
+        };
+        (x.clamp(0, free_w) as usize, y.clamp(0, free_h) as usize)
+    }
+}
+
+/// A string stamped into the heat buffer every frame so it burns like the fire source.
+pub struct HeatText {
+    pub text: String,
+    pub x: usize,
+    pub y: usize,
+    pub size: usize,
+    pub heat: f32,
+}
+
+impl HeatText {
+    /// Builds a heat text from config. Positions are given in screen pixels and mapped onto the fire grid.
+    pub fn from_config(config: &TextConfig, scale: usize) -> Self {
+        let scale = scale.max(1);
+        Self {
+            text: expand_placeholders(&config.text),
+            x: config.x.unwrap_or(0) / scale,
+            y: config.y.unwrap_or(0) / scale,
+            size: config.size.unwrap_or(1).max(1),
+            heat: config.heat.unwrap_or(1.0).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Draws the text into a row-major grid, setting lit cells to `value`.
+    pub fn stamp(&self, buffer: &mut [u8], width: usize, height: usize, value: u8) {
+        let at = Placement { x: self.x, y: self.y, size: self.size };
+        stamp_text(buffer, width, height, &self.text, at, value);
+    }
+
+    /// Returns the heat value the text burns at for a palette of `palette_len` entries.
+    pub fn heat_value(&self, palette_len: usize) -> u8 {
+        let max_heat = palette_len.saturating_sub(1).min(u8::MAX as usize) as f32;
+        (max_heat * self.heat).round() as u8
+    }
+}
+
+/// Returns the width in cells of the widest line of `text` at the given size.
+pub fn text_width(text: &str, size: usize) -> usize {
+    text.lines()
+        .map(|line| {
+            let chars = line.chars().count();
+            (chars * (CHAR_WIDTH + SPACING)).saturating_sub(SPACING) * size
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the height in cells of `text` at the given size.
+pub fn text_height(text: &str, size: usize) -> usize {
+    let lines = text.lines().count();
+    (lines * (CHAR_HEIGHT + SPACING)).saturating_sub(SPACING) * size
+}
+
+/// Where text is stamped: the top-left cell of its first line, and how many cells each font pixel covers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub x: usize,
+    pub y: usize,
+    pub size: usize,
+}
+
+/// Writes `text` into a row-major buffer, setting every lit glyph cell to `value`.
+/// Each font pixel becomes a `size` x `size` block; characters outside the buffer are clipped.
+pub fn stamp_text(buffer: &mut [u8], width: usize, height: usize, text: &str, at: Placement, value: u8) {
+    let Placement { x, y, size } = at;
+    for (line_idx, line) in text.lines().enumerate() {
+        let line_y = y + line_idx * (CHAR_HEIGHT + SPACING) * size;
+        for (i, c) in line.chars().enumerate() {
+            let offset_x = x + i * (CHAR_WIDTH + SPACING) * size;
+            for (dy, row) in glyph(c).iter().enumerate() {
+                for (dx, &on) in row.iter().enumerate() {
+                    if on == 0 {
+                        continue;
+                    }
+                    for sy in 0..size {
+                        for sx in 0..size {
+                            let px = offset_x + dx * size + sx;
+                            let py = line_y + dy * size + sy;
+                            if py < height && px < width {
+                                buffer[py * width + px] = value;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Looks up the glyph for a character, falling back to '?' for anything outside printable ASCII.
+fn glyph(c: char) -> &'static [[u8; 3]; 5] {
+    let idx = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    &FONT[idx]
+}
+
+/// Replaces `{hostname}` with the machine's hostname.
+fn expand_placeholders(text: &str) -> String {
+    if !text.contains("{hostname}") {
+        return text.to_string();
+    }
+    let hostname = std::fs::read_to_string("/etc/hostname")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+    text.replace("{hostname}", &hostname)
+}
@@ -1,4 +1,8 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
 use std::process::Command;
+use std::sync::mpsc;
 
 pub fn is_system_sleeping() -> bool {
     let output = Command::new("systemctl")
@@ -12,8 +16,53 @@ pub fn is_system_sleeping() -> bool {
     }
 }
 
+/// A visible client window, in the local pixel coordinates of the monitor it is on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientRect {
+    pub address: String, // Without the 0x prefix, to match IPC events
+    pub monitor: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// A monitor's position and size in Hyprland's global layout coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorRect {
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64, // Logical size, i.e. physical pixels divided by the monitor scale
+    pub height: f64,
+}
+
+impl MonitorRect {
+    /// Returns where a global layout point falls on this monitor as fractions of its size, if it is on it.
+    pub fn normalize(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let nx = (x - self.x) / self.width;
+        let ny = (y - self.y) / self.height;
+        ((0.0..1.0).contains(&nx) && (0.0..1.0).contains(&ny)).then_some((nx, ny))
+    }
+}
+
+/// What one `hyprctl` round trip tells us about the desktop.
+#[derive(Debug, Default)]
+pub struct HyprState {
+    /// (output_name, is_covered) for all outputs.
+    pub outputs: Vec<(String, bool)>,
+    /// Visible clients on each output's active workspace.
+    pub clients: Vec<ClientRect>,
+    pub monitors: Vec<MonitorRect>,
+}
+
 /// Returns Vec<(output_name, is_covered)> for all outputs.
 pub fn get_outputs_covered() -> Vec<(String, bool)> {
+    query_hypr_state().outputs
+}
+
+/// Queries monitors and clients once and works out coverage and client geometry from them.
+pub fn query_hypr_state() -> HyprState {
     let monitors = std::process::Command::new("hyprctl")
         .args(["monitors", "-j"])
         .output()
@@ -26,11 +75,16 @@ pub fn get_outputs_covered() -> Vec<(String, bool)> {
         .and_then(|o| serde_json::from_slice::<serde_json::Value>(&o.stdout).ok());
     let (monitors, clients) = match (monitors, clients) {
         (Some(m), Some(c)) => (m, c),
-        _ => return vec![],
+        _ => return HyprState::default(),
     };
     let mut result = vec![];
+    let mut rects = vec![];
+    let mut monitor_rects = vec![];
     for monitor in monitors.as_array().unwrap_or(&vec![]) {
         let name = monitor.get("name").and_then(|n| n.as_str()).unwrap_or("").to_string();
+        if let Some(rect) = monitor_rect(monitor, &name) {
+            monitor_rects.push(rect);
+        }
         let ws_id = monitor.get("activeWorkspace")
             .and_then(|ws| ws.get("id").or_else(|| ws.get("name")))
             .cloned();
@@ -55,10 +109,125 @@ pub fn get_outputs_covered() -> Vec<(String, bool)> {
                 .cloned();
             if ws_id == client_ws_id {
                 found = true;
-                break;
+                if let Some(rect) = client_rect(client, monitor, &name) {
+                    rects.push(rect);
+                }
             }
         }
         result.push((name, found));
     }
-    result
-}
\ No newline at end of file
+    HyprState {
+        outputs: result,
+        clients: rects,
+        monitors: monitor_rects,
+    }
+}
+
+/// Reads a monitor's position and its size in logical pixels from a `hyprctl monitors` entry.
+fn monitor_rect(monitor: &serde_json::Value, name: &str) -> Option<MonitorRect> {
+    let field = |key: &str| monitor.get(key).and_then(|v| v.as_f64());
+    let scale = field("scale").unwrap_or(1.0).max(0.1);
+    Some(MonitorRect {
+        name: name.to_string(),
+        x: field("x")?,
+        y: field("y")?,
+        width: field("width")? / scale,
+        height: field("height")? / scale,
+    })
+}
+
+/// Returns the cursor position in global layout coordinates.
+/// Asks the IPC socket directly and falls back to running `hyprctl cursorpos`.
+pub fn get_cursor_position() -> Option<(f64, f64)> {
+    let reply = hypr_request("j/cursorpos").or_else(|| {
+        Command::new("hyprctl")
+            .args(["cursorpos", "-j"])
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+    })?;
+    let json: serde_json::Value = serde_json::from_str(&reply).ok()?;
+    Some((json.get("x")?.as_f64()?, json.get("y")?.as_f64()?))
+}
+
+/// Sends one request to Hyprland's command socket (`.socket.sock`) and returns the reply.
+fn hypr_request(command: &str) -> Option<String> {
+    let mut stream = UnixStream::connect(hypr_socket_dir()?.join(".socket.sock")).ok()?;
+    stream.write_all(command.as_bytes()).ok()?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).ok()?;
+    Some(reply)
+}
+
+/// Finds this Hyprland instance's socket directory.
+fn hypr_socket_dir() -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    [
+        PathBuf::from(runtime_dir).join("hypr").join(&signature),
+        PathBuf::from("/tmp/hypr").join(&signature),
+    ]
+    .into_iter()
+    .find(|dir| dir.exists())
+}
+
+/// Converts a client's global layout position into physical pixels local to its monitor.
+fn client_rect(client: &serde_json::Value, monitor: &serde_json::Value, monitor_name: &str) -> Option<ClientRect> {
+    let pair = |v: Option<&serde_json::Value>| -> Option<(f64, f64)> {
+        let a = v?.as_array()?;
+        Some((a.first()?.as_f64()?, a.get(1)?.as_f64()?))
+    };
+    let (cx, cy) = pair(client.get("at"))?;
+    let (cw, ch) = pair(client.get("size"))?;
+    let mx = monitor.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let my = monitor.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let scale = monitor.get("scale").and_then(|v| v.as_f64()).unwrap_or(1.0);
+    let address = client.get("address").and_then(|a| a.as_str())?;
+    Some(ClientRect {
+        address: address.trim_start_matches("0x").to_string(),
+        monitor: monitor_name.to_string(),
+        x: ((cx - mx) * scale) as i32,
+        y: ((cy - my) * scale) as i32,
+        width: (cw * scale) as i32,
+        height: (ch * scale) as i32,
+    })
+}
+
+/// A window or workspace change reported on Hyprland's event socket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompositorEvent {
+    WindowOpened(String), // Client address, without the 0x prefix
+    WindowClosed(String),
+    WorkspaceChanged,
+}
+
+/// Listens on Hyprland's event socket (`.socket2.sock`) on a background thread.
+/// Returns `None` if not running under Hyprland or the socket can't be opened.
+pub fn spawn_event_listener() -> Option<mpsc::Receiver<CompositorEvent>> {
+    let stream = UnixStream::connect(hypr_socket_dir()?.join(".socket2.sock")).ok()?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if let Some(event) = parse_event(&line)
+                && tx.send(event).is_err()
+            {
+                break;
+            }
+        }
+        eprintln!("[DEBUG] Hyprland event socket closed");
+    });
+    Some(rx)
+}
+
+/// Parses one `EVENT>>DATA` line from the event socket.
+fn parse_event(line: &str) -> Option<CompositorEvent> {
+    let (event, data) = line.split_once(">>")?;
+    match event {
+        // openwindow>>ADDRESS,WORKSPACE,CLASS,TITLE
+        "openwindow" => Some(CompositorEvent::WindowOpened(data.split(',').next()?.to_string())),
+        "closewindow" => Some(CompositorEvent::WindowClosed(data.trim().to_string())),
+        "workspace" => Some(CompositorEvent::WorkspaceChanged),
+        _ => None,
+    }
+}
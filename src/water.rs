@@ -0,0 +1,220 @@
+use crate::burn::CellRect;
+use crate::config::Config;
+use crate::effect::{for_each_in_disc, Effect, Grid, Reactions};
+use crate::particle::ParticleKind;
+use image::{DynamicImage, GenericImageView};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+/// Depth of a raindrop's dent in the surface.
+const DROP_STRENGTH: f32 = 24.0;
+/// How long a screenshot under the water takes to fade back to the plain palette.
+const IMAGE_FADE_SECONDS: f32 = 8.0;
+
+/// Rain on a pond: a 2D wave equation disturbed by raindrops, the cursor and window events.
+/// Slopes in the surface shade it through the palette and refract the screenshot of a closed window underneath.
+pub struct Water {
+    grid: Grid,
+    current: Vec<f32>,  // Surface height this frame
+    previous: Vec<f32>, // Surface height last frame
+    image: Vec<[u8; 4]>, // Screenshot under the water, with its opacity
+    layer: Vec<[u8; 4]>, // `image` as seen through the ripples
+    image_fade: f32,     // Opacity still to take off `image`, carried between frames
+    rain: f32,
+    damping: f32,
+    refraction: f32,
+    rain_due: f32, // Fractional drops carried between frames
+    intensity: f32,
+    frame_time: f32,
+    rng: ThreadRng,
+}
+
+impl Water {
+    pub fn new(config: &Config) -> Self {
+        let scale = config.scale.unwrap_or(1).max(1);
+        let water = config.water.as_ref();
+        let mut pond = Self {
+            grid: Grid::new(config),
+            current: Vec::new(),
+            previous: Vec::new(),
+            image: Vec::new(),
+            layer: Vec::new(),
+            image_fade: 0.0,
+            rain: water.and_then(|w| w.rain).unwrap_or(3.0).max(0.0),
+            damping: water.and_then(|w| w.damping).unwrap_or(0.985).clamp(0.5, 0.999),
+            refraction: water.and_then(|w| w.refraction).unwrap_or(1.0).max(0.0),
+            rain_due: 0.0,
+            intensity: 1.0,
+            frame_time: 1.0 / config.fps.unwrap_or(10).max(1) as f32,
+            rng: rand::rng(),
+        };
+        pond.resize(
+            config.screen_width.unwrap_or(1920) / scale,
+            config.screen_height.unwrap_or(1080) / scale,
+        );
+        pond
+    }
+
+    /// Pushes the surface down in a small disc around (`x`, `y`); negative strength lifts it.
+    fn splash(&mut self, x: usize, y: usize, radius: usize, strength: f32) {
+        for_each_in_disc(self.grid.width, self.grid.height, (x, y), radius, |idx, falloff| {
+            if !self.grid.solid[idx] {
+                self.current[idx] -= strength * falloff;
+            }
+        });
+    }
+
+    /// Advances the wave equation by one step.
+    fn propagate(&mut self) {
+        let w = self.grid.width;
+        for y in 1..self.grid.height.saturating_sub(1) {
+            for x in 1..w.saturating_sub(1) {
+                let i = y * w + x;
+                if self.grid.solid[i] {
+                    self.previous[i] = 0.0;
+                    continue;
+                }
+                let neighbours = self.current[i - 1] + self.current[i + 1] + self.current[i - w] + self.current[i + w];
+                self.previous[i] = (neighbours / 2.0 - self.previous[i]) * self.damping;
+            }
+        }
+        std::mem::swap(&mut self.current, &mut self.previous);
+    }
+
+    /// Shades the surface into `heat` by its slope and looks up the refracted screenshot for `layer`.
+    fn render(&mut self) {
+        let (w, h) = (self.grid.width, self.grid.height);
+        let max_heat = self.max_heat() as f32;
+        // Calm water sits a third of the way up the palette; slopes facing the light are brighter
+        let base = max_heat / 3.0;
+        let has_image = self.image.iter().any(|p| p[3] > 0);
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                if self.grid.solid[i] {
+                    self.grid.heat[i] = 0;
+                    self.layer[i] = [0; 4];
+                    continue;
+                }
+                let at = |x: usize, y: usize| self.current[y * w + x];
+                let dx = at(x.saturating_sub(1), y) - at((x + 1).min(w - 1), y);
+                let dy = at(x, y.saturating_sub(1)) - at(x, (y + 1).min(h - 1));
+                self.grid.heat[i] = (base + (dx + dy) * 2.0).clamp(1.0, max_heat).round() as u8;
+                if has_image {
+                    let sx = (x as f32 + dx * self.refraction).clamp(0.0, (w - 1) as f32) as usize;
+                    let sy = (y as f32 + dy * self.refraction).clamp(0.0, (h - 1) as f32) as usize;
+                    self.layer[i] = self.image[sy * w + sx];
+                }
+            }
+        }
+        if !has_image {
+            self.layer.iter_mut().for_each(|p| *p = [0; 4]);
+        }
+    }
+
+    fn fade_image(&mut self, dt: f32) {
+        self.image_fade += 255.0 * dt / IMAGE_FADE_SECONDS;
+        let step = self.image_fade as u8;
+        self.image_fade -= step as f32;
+        if step > 0 {
+            self.image.iter_mut().for_each(|p| p[3] = p[3].saturating_sub(step));
+        }
+    }
+}
+
+impl Effect for Water {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    fn image_layer(&self) -> Option<&[[u8; 4]]> {
+        Some(&self.layer)
+    }
+
+    fn update(&mut self) {
+        if self.grid.width == 0 || self.grid.height == 0 {
+            return;
+        }
+        let dt = self.frame_time * self.intensity;
+        self.rain_due += self.rain * dt;
+        while self.rain_due >= 1.0 {
+            self.rain_due -= 1.0;
+            let x = self.rng.random_range(0..self.grid.width);
+            let y = self.rng.random_range(0..self.grid.height);
+            self.splash(x, y, 2, DROP_STRENGTH);
+        }
+        self.propagate();
+        self.fade_image(dt);
+        self.render();
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn clear(&mut self) {
+        self.grid.heat.iter_mut().for_each(|x| *x = 0);
+        self.current.iter_mut().for_each(|x| *x = 0.0);
+        self.previous.iter_mut().for_each(|x| *x = 0.0);
+        self.image.iter_mut().for_each(|p| *p = [0; 4]);
+        self.layer.iter_mut().for_each(|p| *p = [0; 4]);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        let size = width * height;
+        self.grid.resize(width, height);
+        self.current = vec![0.0; size];
+        self.previous = vec![0.0; size];
+        self.image = vec![[0; 4]; size];
+        self.layer = vec![[0; 4]; size];
+    }
+
+    /// Heat pushes the surface down a little, so burning edges ripple the water.
+    fn add_heat(&mut self, x: usize, y: usize, heat: u8) {
+        if x >= self.grid.width || y >= self.grid.height {
+            return;
+        }
+        let idx = y * self.grid.width + x;
+        if !self.grid.solid[idx] {
+            self.current[idx] -= heat as f32 / self.max_heat().max(1) as f32;
+        }
+    }
+
+    /// The cursor drags through the water: torch presses the surface down, cold lifts it.
+    fn apply_heat_brush(&mut self, x: usize, y: usize, radius: usize, amount: f32) {
+        self.splash(x, y, radius.min(4), amount * 4.0);
+    }
+}
+
+impl Reactions for Water {
+    /// The screenshot sinks under the water with a splash, instead of burning.
+    fn show_screenshot(&mut self, screenshot: &DynamicImage, rect: CellRect) -> bool {
+        let resized = screenshot.resize_exact(
+            self.grid.width as u32,
+            self.grid.height as u32,
+            image::imageops::FilterType::Triangle,
+        );
+        for y in rect.y..(rect.y + rect.height).min(self.grid.height) {
+            for x in rect.x..(rect.x + rect.width).min(self.grid.width) {
+                let px = resized.get_pixel(x as u32, y as u32);
+                self.image[y * self.grid.width + x] = [px[0], px[1], px[2], 255];
+            }
+        }
+        let radius = (rect.width.min(rect.height) / 4).max(2);
+        self.splash(rect.x + rect.width / 2, rect.y + rect.height / 2, radius, DROP_STRENGTH * 2.0);
+        true
+    }
+
+    /// Sparks and embers land as a splash; bigger bursts splash harder.
+    fn spawn_burst(&mut self, _kind: ParticleKind, x: usize, y: usize, count: usize) {
+        self.splash(x, y, 3, DROP_STRENGTH * (count as f32 / 4.0).clamp(0.5, 3.0));
+    }
+
+    fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity.max(0.0) as f32;
+    }
+}
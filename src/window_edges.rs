@@ -0,0 +1,30 @@
+use crate::effect::Effect;
+use crate::wallpaper::ClientRect;
+
+/// Feeds heat along the top and side edges of every visible window, so flames rise up their borders.
+/// Windows sit above the wallpaper, so anything burning behind them stays hidden.
+pub fn burn_window_edges(fire: &mut dyn Effect, clients: &[ClientRect], scale: usize) {
+    let scale = scale.max(1) as i32;
+    let max_heat = fire.max_heat();
+    // Sides burn a little cooler than the top so the top edge reads as the main flame front
+    let side_heat = (max_heat as f32 * 0.8) as u8;
+    for rect in clients {
+        let left = rect.x / scale - 1;
+        let right = (rect.x + rect.width) / scale;
+        let top = rect.y / scale - 1;
+        let bottom = (rect.y + rect.height) / scale;
+        for x in left..=right {
+            heat_cell(fire, x, top, max_heat);
+        }
+        for y in top..bottom {
+            heat_cell(fire, left, y, side_heat);
+            heat_cell(fire, right, y, side_heat);
+        }
+    }
+}
+
+fn heat_cell(fire: &mut dyn Effect, x: i32, y: i32, heat: u8) {
+    if x >= 0 && y >= 0 {
+        fire.add_heat(x as usize, y as usize, heat);
+    }
+}
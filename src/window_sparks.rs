@@ -0,0 +1,88 @@
+use crate::effect::Effect;
+use crate::particle::ParticleKind;
+use crate::wallpaper::{ClientRect, CompositorEvent};
+use std::collections::HashMap;
+
+const OPEN_RETRY_FRAMES: u8 = 10; // New windows can take a few frames to show up in `hyprctl clients`
+const CLOSE_GRACE_FRAMES: u8 = 10; // A closed window can leave `hyprctl clients` a few frames before its event arrives
+const GUST_STRENGTH: f64 = 4.0;
+
+/// Turns window open/close and workspace events into sparks, embers and wind gusts.
+pub struct WindowSparks {
+    scale: usize,
+    /// Last known geometry per client, so closed windows can still be located, and refreshes since it was listed.
+    clients: HashMap<String, (ClientRect, u8)>,
+    /// Opened windows whose geometry isn't known yet, with frames left to wait.
+    pending_opens: Vec<(String, u8)>,
+}
+
+impl WindowSparks {
+    pub fn new(scale: usize) -> Self {
+        Self {
+            scale: scale.max(1),
+            clients: HashMap::new(),
+            pending_opens: Vec::new(),
+        }
+    }
+
+    /// Applies this frame's events. `clients` is the current client list from `query_hypr_state`.
+    pub fn update(&mut self, clients: &[ClientRect], events: Vec<CompositorEvent>, fire: &mut dyn Effect) {
+        for event in events {
+            match event {
+                CompositorEvent::WindowClosed(address) => {
+                    if let Some((rect, _)) = self.clients.remove(&address) {
+                        self.burst_from_bottom(&rect, fire);
+                    }
+                }
+                CompositorEvent::WindowOpened(address) => self.pending_opens.push((address, OPEN_RETRY_FRAMES)),
+                CompositorEvent::WorkspaceChanged => fire.gust(GUST_STRENGTH),
+            }
+        }
+
+        // Forget windows that closed without an event, e.g. while the event socket was down
+        self.clients.retain(|_, (_, missed)| {
+            *missed += 1;
+            *missed <= CLOSE_GRACE_FRAMES
+        });
+        for rect in clients {
+            self.clients.insert(rect.address.clone(), (rect.clone(), 0));
+        }
+
+        let mut pending = std::mem::take(&mut self.pending_opens);
+        pending.retain_mut(|(address, frames_left)| {
+            if let Some(rect) = clients.iter().find(|c| &c.address == address) {
+                self.puff_embers(rect, fire);
+                return false;
+            }
+            *frames_left -= 1;
+            *frames_left > 0
+        });
+        self.pending_opens = pending;
+    }
+
+    /// Throws sparks up from along the bottom edge of a window that just closed.
+    fn burst_from_bottom(&self, rect: &ClientRect, fire: &mut dyn Effect) {
+        let Some((x0, x1, y)) = self.bottom_edge(rect, fire) else { return };
+        for x in (x0..x1).step_by(6) {
+            fire.spawn_burst(ParticleKind::Spark, x, y, 3);
+        }
+    }
+
+    /// Sends a puff of embers up from the middle of the bottom edge of a window that just opened.
+    fn puff_embers(&self, rect: &ClientRect, fire: &mut dyn Effect) {
+        let Some((x0, x1, y)) = self.bottom_edge(rect, fire) else { return };
+        fire.spawn_burst(ParticleKind::Ember, (x0 + x1) / 2, y, 12);
+    }
+
+    /// Returns the window's bottom edge as (first column, end column, row) on the fire grid, if visible.
+    fn bottom_edge(&self, rect: &ClientRect, fire: &dyn Effect) -> Option<(usize, usize, usize)> {
+        let scale = self.scale as i32;
+        let x0 = (rect.x / scale).clamp(0, fire.width() as i32);
+        let x1 = ((rect.x + rect.width) / scale).clamp(0, fire.width() as i32);
+        let y = (rect.y + rect.height) / scale;
+        if x1 <= x0 || y < 0 || y >= fire.height() as i32 {
+            return None;
+        }
+        Some((x0 as usize, x1 as usize, y as usize))
+    }
+}